@@ -0,0 +1,375 @@
+use csv::{ReaderBuilder, StringRecord};
+use flate2::bufread::GzDecoder;
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+use crate::compression::Compression;
+
+/// Файл, в который дописываются только целые записи: каждая запись
+/// пишется одним вызовом и сразу сбрасывается на диск. Если имя файла
+/// оканчивается на `.gz` или `.zst`, каждая запись сжимается отдельным фреймом.
+pub struct AppendFile {
+    file: File,
+    path: PathBuf,
+    compression: Compression,
+}
+
+impl AppendFile {
+    pub fn open(path: &Path) -> Result<Self, String> {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(|e| format!("Не удалось открыть {:?}: {}", path, e))?;
+
+        Ok(Self {
+            file,
+            path: path.to_path_buf(),
+            compression: Compression::of(path),
+        })
+    }
+
+    pub fn is_empty(&self) -> Result<bool, String> {
+        self.file
+            .metadata()
+            .map(|metadata| metadata.len() == 0)
+            .map_err(|e| format!("{:?}: {}", self.path, e))
+    }
+
+    pub fn append(&mut self, record: &[u8]) -> Result<(), String> {
+        let frame = self
+            .compression
+            .compress(record)
+            .map_err(|e| format!("Не удалось сжать запись {:?}: {}", self.path, e))?;
+        self.file
+            .write_all(&frame)
+            .and_then(|_| self.file.sync_data())
+            .map_err(|e| format!("Не удалось записать {:?}: {}", self.path, e))
+    }
+
+    /// Отрезает всё, начиная с позиции `len`
+    pub fn truncate(path: &Path, len: u64) -> Result<(), String> {
+        OpenOptions::new()
+            .write(true)
+            .open(path)
+            .and_then(|file| file.set_len(len).and_then(|_| file.sync_data()))
+            .map_err(|e| format!("Не удалось обрезать {:?}: {}", path, e))
+    }
+
+    /// Обрезает недописанную последнюю строку (для сжатого файла - фрейм),
+    /// оставшуюся после аварийного завершения. Строка CSV может содержать
+    /// перевод строки внутри поля в кавычках, поэтому CSV-файл обрезается по
+    /// границе строки CSV. Возвращает количество отброшенных байт.
+    pub fn repair(path: &Path) -> Result<u64, String> {
+        if !path.exists() {
+            return Ok(0);
+        }
+        let valid_len = if Compression::of(path) != Compression::None {
+            let mut records = Records::open(path)
+                .map_err(|e| format!("Не удалось восстановить {:?}: {}", path, e))?;
+            records.by_ref().for_each(drop);
+            records.position
+        } else if path.extension().is_some_and(|ext| ext == "csv") {
+            scan_csv(path, |_, _| Ok(()))?
+        } else {
+            last_line_end(path).map_err(|e| format!("Не удалось восстановить {:?}: {}", path, e))?
+        };
+
+        let len = fs::metadata(path)
+            .map_err(|e| format!("Не удалось восстановить {:?}: {}", path, e))?
+            .len();
+        if valid_len < len {
+            Self::truncate(path, valid_len)?;
+        }
+
+        Ok(len - valid_len)
+    }
+}
+
+/// Позиция сразу после последнего перевода строки
+fn last_line_end(path: &Path) -> std::io::Result<u64> {
+    let mut file = File::open(path)?;
+    let mut buffer = [0u8; 8192];
+    let mut end = file.metadata()?.len();
+    while end > 0 {
+        let start = end.saturating_sub(buffer.len() as u64);
+        let chunk = &mut buffer[..(end - start) as usize];
+        file.seek(SeekFrom::Start(start))?;
+        file.read_exact(chunk)?;
+        if let Some(idx) = chunk.iter().rposition(|byte| *byte == b'\n') {
+            return Ok(start + idx as u64 + 1);
+        }
+        end = start;
+    }
+
+    Ok(0)
+}
+
+/// Обходит целые строки CSV-файла (включая заголовок) вместе с позицией
+/// записи файла, в которой лежит строка: для сжатого файла - начала фрейма,
+/// для несжатого - начала строки CSV. Поле в кавычках может содержать
+/// перевод строки, поэтому границы строк несжатого файла берутся у
+/// `csv::Reader`. Обход останавливается на недописанной последней строке:
+/// без завершающего перевода строки или с неполным набором полей.
+/// Возвращает длину целой части файла.
+pub fn scan_csv(
+    path: &Path,
+    mut visit: impl FnMut(u64, &StringRecord) -> Result<(), String>,
+) -> Result<u64, String> {
+    let map_err = |e: &dyn std::fmt::Display| format!("{:?}: {}", path, e);
+
+    if Compression::of(path) != Compression::None {
+        let mut records = Records::open(path).map_err(|e| map_err(&e))?;
+        for (start, record) in records.by_ref() {
+            let mut reader = ReaderBuilder::new()
+                .has_headers(false)
+                .from_reader(record.as_slice());
+            for row in reader.records() {
+                visit(start, &row.map_err(|e| map_err(&e))?)?;
+            }
+        }
+        return Ok(records.position);
+    }
+
+    let mut file = File::open(path).map_err(|e| map_err(&e))?;
+    let len = file.metadata().map_err(|e| map_err(&e))?.len();
+    let mut last = [b'\n'];
+    if len > 0 {
+        file.seek(SeekFrom::End(-1))
+            .and_then(|_| file.read_exact(&mut last))
+            .and_then(|_| file.rewind())
+            .map_err(|e| map_err(&e))?;
+    }
+
+    // Число полей сверяется с первой строкой (заголовком): строка, оборванная
+    // после перевода строки внутри поля в кавычках, короче заголовка
+    let mut reader = ReaderBuilder::new().has_headers(false).from_reader(file);
+    let mut row = StringRecord::new();
+    let mut valid_len = 0;
+    loop {
+        match reader.read_record(&mut row) {
+            Ok(true) => {}
+            Ok(false) => break,
+            // Ошибка в последней строке - обрыв записи, в середине - порча файла
+            Err(e) => {
+                if reader.records().next().is_some() {
+                    return Err(map_err(&e));
+                }
+                break;
+            }
+        }
+        let end = reader.position().byte();
+        if end == len && last[0] != b'\n' {
+            break;
+        }
+        visit(valid_len, &row)?;
+        valid_len = end;
+    }
+
+    Ok(valid_len)
+}
+
+/// Целые записи файла с позицией начала каждой из них: для несжатого файла
+/// запись - строка, для сжатого - фрейм. Чтение останавливается на первой
+/// недописанной или повреждённой записи. Строка несжатого CSV-файла может
+/// занимать несколько строк файла, такие файлы читаются через `scan_csv`.
+pub struct Records {
+    reader: BufReader<File>,
+    compression: Compression,
+    position: u64,
+    finished: bool,
+}
+
+impl Records {
+    pub fn open(path: &Path) -> std::io::Result<Self> {
+        Ok(Self {
+            reader: BufReader::new(File::open(path)?),
+            compression: Compression::of(path),
+            position: 0,
+            finished: false,
+        })
+    }
+
+    fn at_end(&mut self) -> bool {
+        self.reader
+            .fill_buf()
+            .map_or(true, |buffer| buffer.is_empty())
+    }
+}
+
+impl Iterator for Records {
+    type Item = (u64, Vec<u8>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished || self.at_end() {
+            return None;
+        }
+
+        let mut record = vec![];
+        let complete = match self.compression {
+            Compression::None => {
+                self.reader.read_until(b'\n', &mut record).is_ok() && record.ends_with(b"\n")
+            }
+            Compression::Gzip => GzDecoder::new(&mut self.reader)
+                .read_to_end(&mut record)
+                .is_ok(),
+            Compression::Zstd => zstd::Decoder::with_buffer(&mut self.reader)
+                .and_then(|decoder| decoder.single_frame().read_to_end(&mut record))
+                .is_ok(),
+        };
+        if !complete {
+            self.finished = true;
+            return None;
+        }
+
+        let start = self.position;
+        self.position = self.reader.stream_position().ok()?;
+        Some((start, record))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::process;
+
+    const COMPRESSIONS: [Compression; 3] =
+        [Compression::None, Compression::Gzip, Compression::Zstd];
+
+    fn temp_path(test: &str, compression: Compression, name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!(
+            "append_file-{}-{}-{:?}",
+            process::id(),
+            test,
+            compression
+        ));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir.join(compression.file_name(name))
+    }
+
+    fn content(path: &Path) -> String {
+        let mut content = String::new();
+        Compression::reader(path)
+            .unwrap()
+            .read_to_string(&mut content)
+            .unwrap();
+        content
+    }
+
+    /// Дописывает начало следующей записи, как при аварийном завершении
+    fn append_torn(path: &Path, record: &[u8], len: usize) {
+        let frame = Compression::of(path).compress(record).unwrap();
+        let mut file = OpenOptions::new().append(true).open(path).unwrap();
+        file.write_all(&frame[..len.min(frame.len() - 1)]).unwrap();
+    }
+
+    fn csv_record(fields: &[&str]) -> Vec<u8> {
+        let mut writer = csv::Writer::from_writer(vec![]);
+        writer.write_record(fields).unwrap();
+        writer.into_inner().unwrap()
+    }
+
+    #[test]
+    fn records_round_trip() {
+        for compression in COMPRESSIONS {
+            let path = temp_path("round_trip", compression, "log.txt");
+            let mut file = AppendFile::open(&path).unwrap();
+            file.append(b"first\n").unwrap();
+            file.append(b"second\n").unwrap();
+
+            let records: Vec<(u64, Vec<u8>)> = Records::open(&path).unwrap().collect();
+            let data: Vec<&[u8]> = records
+                .iter()
+                .map(|(_, record)| record.as_slice())
+                .collect();
+            assert_eq!(
+                data,
+                [&b"first\n"[..], &b"second\n"[..]],
+                "{:?}",
+                compression
+            );
+            assert_eq!(records[0].0, 0);
+            assert_eq!(
+                records[1].0,
+                compression.compress(b"first\n").unwrap().len() as u64
+            );
+            assert_eq!(content(&path), "first\nsecond\n");
+        }
+    }
+
+    #[test]
+    fn repair_drops_torn_tail() {
+        for compression in COMPRESSIONS {
+            let path = temp_path("torn_tail", compression, "log.txt");
+            let mut file = AppendFile::open(&path).unwrap();
+            file.append(b"first\n").unwrap();
+            file.append(b"second\n").unwrap();
+            let len = fs::metadata(&path).unwrap().len();
+            append_torn(&path, b"third\n", 10);
+
+            assert!(AppendFile::repair(&path).unwrap() > 0, "{:?}", compression);
+            assert_eq!(fs::metadata(&path).unwrap().len(), len);
+            assert_eq!(AppendFile::repair(&path).unwrap(), 0);
+
+            let mut file = AppendFile::open(&path).unwrap();
+            file.append(b"third\n").unwrap();
+            assert_eq!(content(&path), "first\nsecond\nthird\n");
+        }
+    }
+
+    #[test]
+    fn csv_quoted_newline() {
+        for compression in COMPRESSIONS {
+            let path = temp_path("quoted_newline", compression, "dataset.csv");
+            let mut file = AppendFile::open(&path).unwrap();
+            let rows: [&[&str]; 3] = [
+                &["instance_id", "error", "calculation_time"],
+                &["a", "panicked at\nsrc/lib.rs:1", "1"],
+                &["b", "", "2"],
+            ];
+            let mut starts = vec![];
+            for row in rows {
+                starts.push(fs::metadata(&path).unwrap().len());
+                file.append(&csv_record(row)).unwrap();
+            }
+            let len = fs::metadata(&path).unwrap().len();
+
+            // Обрыв сразу после перевода строки внутри поля в кавычках
+            let torn = csv_record(&["c", "panicked at\nsrc/lib.rs:2", "3"]);
+            let cut = torn.iter().position(|byte| *byte == b'\n').unwrap() + 1;
+            append_torn(&path, &torn, cut);
+
+            assert!(AppendFile::repair(&path).unwrap() > 0, "{:?}", compression);
+            assert_eq!(fs::metadata(&path).unwrap().len(), len);
+
+            let mut scanned: Vec<(u64, Vec<String>)> = vec![];
+            let valid_len = scan_csv(&path, |start, row| {
+                scanned.push((start, row.iter().map(str::to_string).collect()));
+                Ok(())
+            })
+            .unwrap();
+            assert_eq!(valid_len, len);
+            assert_eq!(
+                scanned
+                    .iter()
+                    .map(|(start, _)| *start)
+                    .collect::<Vec<u64>>(),
+                starts
+            );
+            assert_eq!(scanned[1].1[1], "panicked at\nsrc/lib.rs:1");
+            assert_eq!(scanned[2].1[0], "b");
+        }
+    }
+
+    #[test]
+    fn csv_unterminated_last_row() {
+        let path = temp_path("unterminated", Compression::None, "dataset.csv");
+        fs::write(&path, "instance_id,calculation_time\na,1\nb,2").unwrap();
+
+        assert_eq!(AppendFile::repair(&path).unwrap(), 3);
+        assert_eq!(content(&path), "instance_id,calculation_time\na,1\n");
+    }
+}
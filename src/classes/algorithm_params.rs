@@ -1,5 +1,9 @@
+use serde::de::{Deserialize, Deserializer};
 use serde::ser::{Serialize, SerializeStruct, Serializer};
 
+pub const MUTATE_FUNCS: [&str; 2] = ["swap_indexes", "reverse_elements"];
+pub const SELECT_FUNCS: [&str; 3] = ["tournament", "roulette", "best_n"];
+
 #[derive(Clone, Debug)]
 pub enum AlgorithmParams {
     AC {
@@ -87,3 +91,230 @@ impl Serialize for AlgorithmParams {
         }
     }
 }
+
+#[derive(serde::Deserialize)]
+#[serde(tag = "type")]
+enum AlgorithmParamsDef {
+    AC {
+        alpha: f64,
+        beta: f64,
+        q: f64,
+        p: f64,
+        actors_count: usize,
+    },
+    BC {
+        workers_part: f32,
+        #[serde(alias = "research_func")]
+        regenerate_func: String,
+        actors_count: usize,
+    },
+    GA {
+        p_mutation: f32,
+        select_func: String,
+        mutate_func: String,
+        actors_count: usize,
+    },
+    SA {
+        initial_temperature: f64,
+        final_temperature: f64,
+        cooling_rate: f64,
+        mutate_func: String,
+    },
+}
+
+// Параметры загружаются один раз при старте, поэтому строки можно оставить
+// в памяти до конца работы и не менять `&'static str` в самих параметрах
+fn leak(value: String) -> &'static str {
+    Box::leak(value.into_boxed_str())
+}
+
+impl<'de> Deserialize<'de> for AlgorithmParams {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let params = match AlgorithmParamsDef::deserialize(deserializer)? {
+            AlgorithmParamsDef::AC {
+                alpha,
+                beta,
+                q,
+                p,
+                actors_count,
+            } => AlgorithmParams::AC {
+                alpha,
+                beta,
+                q,
+                p,
+                actors_count,
+            },
+            AlgorithmParamsDef::BC {
+                workers_part,
+                regenerate_func,
+                actors_count,
+            } => AlgorithmParams::BC {
+                workers_part,
+                research_func: leak(regenerate_func),
+                actors_count,
+            },
+            AlgorithmParamsDef::GA {
+                p_mutation,
+                select_func,
+                mutate_func,
+                actors_count,
+            } => AlgorithmParams::GA {
+                p_mutation,
+                select_func: leak(select_func),
+                mutate_func: leak(mutate_func),
+                actors_count,
+            },
+            AlgorithmParamsDef::SA {
+                initial_temperature,
+                final_temperature,
+                cooling_rate,
+                mutate_func,
+            } => AlgorithmParams::SA {
+                initial_temperature,
+                final_temperature,
+                cooling_rate,
+                mutate_func: leak(mutate_func),
+            },
+        };
+
+        Ok(params)
+    }
+}
+
+impl AlgorithmParams {
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            AlgorithmParams::AC { .. } => "AC",
+            AlgorithmParams::BC { .. } => "BC",
+            AlgorithmParams::GA { .. } => "GA",
+            AlgorithmParams::SA { .. } => "SA",
+        }
+    }
+
+    /// Сколько решений алгоритм оценивает за итерацию: по одному на каждого
+    /// агента популяции, у имитации отжига - одно
+    pub fn evaluations_per_iteration(&self) -> usize {
+        match self {
+            AlgorithmParams::AC { actors_count, .. }
+            | AlgorithmParams::BC { actors_count, .. }
+            | AlgorithmParams::GA { actors_count, .. } => *actors_count,
+            AlgorithmParams::SA { .. } => 1,
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        let mut errors: Vec<String> = vec![];
+
+        match self {
+            AlgorithmParams::AC {
+                alpha,
+                beta,
+                q,
+                p,
+                actors_count,
+            } => {
+                if !alpha.is_finite() || *alpha < 0.0 {
+                    errors.push(format!("alpha должно быть >= 0, получено {}", alpha));
+                }
+                if !beta.is_finite() || *beta < 0.0 {
+                    errors.push(format!("beta должно быть >= 0, получено {}", beta));
+                }
+                if !q.is_finite() || *q <= 0.0 {
+                    errors.push(format!("q должно быть > 0, получено {}", q));
+                }
+                if !(*p > 0.0 && *p <= 1.0) {
+                    errors.push(format!("p должно быть в (0, 1], получено {}", p));
+                }
+                if *actors_count == 0 {
+                    errors.push("actors_count должно быть > 0".to_string());
+                }
+            }
+            AlgorithmParams::BC {
+                workers_part,
+                research_func,
+                actors_count,
+            } => {
+                if !(*workers_part > 0.0 && *workers_part < 1.0) {
+                    errors.push(format!(
+                        "workers_part должно быть в (0, 1), получено {}",
+                        workers_part
+                    ));
+                }
+                if !MUTATE_FUNCS.contains(research_func) {
+                    errors.push(format!(
+                        "неизвестная regenerate_func {:?}, ожидается одна из {:?}",
+                        research_func, MUTATE_FUNCS
+                    ));
+                }
+                if *actors_count == 0 {
+                    errors.push("actors_count должно быть > 0".to_string());
+                }
+            }
+            AlgorithmParams::GA {
+                p_mutation,
+                select_func,
+                mutate_func,
+                actors_count,
+            } => {
+                if !(*p_mutation >= 0.0 && *p_mutation <= 1.0) {
+                    errors.push(format!("p_mutation должно быть в [0, 1], получено {}", p_mutation));
+                }
+                if !SELECT_FUNCS.contains(select_func) {
+                    errors.push(format!(
+                        "неизвестная select_func {:?}, ожидается одна из {:?}",
+                        select_func, SELECT_FUNCS
+                    ));
+                }
+                if !MUTATE_FUNCS.contains(mutate_func) {
+                    errors.push(format!(
+                        "неизвестная mutate_func {:?}, ожидается одна из {:?}",
+                        mutate_func, MUTATE_FUNCS
+                    ));
+                }
+                if *actors_count < 2 {
+                    errors.push(format!("actors_count должно быть >= 2, получено {}", actors_count));
+                }
+            }
+            AlgorithmParams::SA {
+                initial_temperature,
+                final_temperature,
+                cooling_rate,
+                mutate_func,
+            } => {
+                if !final_temperature.is_finite() || *final_temperature <= 0.0 {
+                    errors.push(format!(
+                        "final_temperature должно быть > 0, получено {}",
+                        final_temperature
+                    ));
+                }
+                if !initial_temperature.is_finite() || *initial_temperature <= *final_temperature {
+                    errors.push(format!(
+                        "initial_temperature ({}) должно быть больше final_temperature ({})",
+                        initial_temperature, final_temperature
+                    ));
+                }
+                if !(*cooling_rate > 0.0 && *cooling_rate < 1.0) {
+                    errors.push(format!(
+                        "cooling_rate должно быть в (0, 1), получено {}",
+                        cooling_rate
+                    ));
+                }
+                if !MUTATE_FUNCS.contains(mutate_func) {
+                    errors.push(format!(
+                        "неизвестная mutate_func {:?}, ожидается одна из {:?}",
+                        mutate_func, MUTATE_FUNCS
+                    ));
+                }
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors.join("; "))
+        }
+    }
+}
@@ -0,0 +1,266 @@
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+
+use super::algorithm_params::AlgorithmParams;
+
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SweepMode {
+    #[default]
+    Cartesian,
+    OneFactor,
+    LatinHypercube,
+}
+
+/// Описание перебора гиперпараметров одного семейства алгоритмов:
+/// `base` задаёт значения по умолчанию, `grid` - уровни варьируемых параметров.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Sweep {
+    #[serde(rename = "type")]
+    pub algorithm: String,
+    #[serde(default)]
+    pub mode: SweepMode,
+    #[serde(default)]
+    pub base: Map<String, Value>,
+    pub grid: BTreeMap<String, Vec<Value>>,
+    pub samples: Option<usize>,
+    #[serde(default)]
+    pub seed: u64,
+}
+
+impl Sweep {
+    pub fn expand(&self) -> Result<Vec<AlgorithmParams>, String> {
+        if let Some((name, _)) = self.grid.iter().find(|(_, levels)| levels.is_empty()) {
+            return Err(format!("grid.{}: нет значений", name));
+        }
+
+        let points = match self.mode {
+            SweepMode::Cartesian => self.cartesian(),
+            SweepMode::OneFactor => self.one_factor()?,
+            SweepMode::LatinHypercube => self.latin_hypercube()?,
+        };
+
+        let mut result: Vec<AlgorithmParams> = Vec::with_capacity(points.len());
+        let mut seen: Vec<Value> = Vec::with_capacity(points.len());
+
+        for point in points {
+            let params = self.to_params(point)?;
+            let key = serde_json::to_value(&params).map_err(|e| e.to_string())?;
+            if !seen.contains(&key) {
+                seen.push(key);
+                result.push(params);
+            }
+        }
+
+        Ok(result)
+    }
+
+    fn to_params(&self, point: Map<String, Value>) -> Result<AlgorithmParams, String> {
+        let mut object = self.base.clone();
+        object.extend(point);
+        object.insert("type".to_string(), Value::String(self.algorithm.clone()));
+
+        let value = Value::Object(object);
+        serde_json::from_value(value.clone()).map_err(|e| format!("{}: {}", value, e))
+    }
+
+    fn cartesian(&self) -> Vec<Map<String, Value>> {
+        self.grid
+            .iter()
+            .fold(vec![Map::new()], |points, (name, levels)| {
+                points
+                    .iter()
+                    .flat_map(|point| {
+                        levels.iter().map(move |level| {
+                            let mut next = point.clone();
+                            next.insert(name.clone(), level.clone());
+                            next
+                        })
+                    })
+                    .collect()
+            })
+    }
+
+    fn one_factor(&self) -> Result<Vec<Map<String, Value>>, String> {
+        if let Some(name) = self.grid.keys().find(|name| !self.base.contains_key(*name)) {
+            return Err(format!(
+                "для режима one_factor нужно базовое значение {} в base",
+                name
+            ));
+        }
+
+        let mut points = vec![Map::new()];
+        for (name, levels) in &self.grid {
+            for level in levels {
+                let mut point = Map::new();
+                point.insert(name.clone(), level.clone());
+                points.push(point);
+            }
+        }
+
+        Ok(points)
+    }
+
+    /// Латинский гиперкуб по дискретным уровням: каждая ось делится на
+    /// `samples` страт, и каждая страта используется ровно один раз.
+    /// Если у осей меньше уровней, чем `samples`, страты попадают на одни и те же
+    /// уровни, поэтому гиперкуб строится заново, пока не наберётся `samples`
+    /// различных точек (но не больше, чем всего точек в сетке).
+    fn latin_hypercube(&self) -> Result<Vec<Map<String, Value>>, String> {
+        let samples = match self.samples {
+            Some(n) if n > 0 => n,
+            _ => return Err("для режима latin_hypercube нужно samples > 0".to_string()),
+        };
+        let grid_size = self
+            .grid
+            .values()
+            .fold(1usize, |size, levels| size.saturating_mul(levels.len()));
+        let target = samples.min(grid_size);
+
+        let mut rng = StdRng::seed_from_u64(self.seed);
+        let mut points: Vec<Map<String, Value>> = Vec::with_capacity(target);
+
+        while points.len() < target {
+            for point in self.hypercube(samples, &mut rng) {
+                if points.len() < target && !points.contains(&point) {
+                    points.push(point);
+                }
+            }
+        }
+
+        Ok(points)
+    }
+
+    fn hypercube(&self, samples: usize, rng: &mut StdRng) -> Vec<Map<String, Value>> {
+        let mut points = vec![Map::new(); samples];
+
+        for (name, levels) in &self.grid {
+            let mut strata: Vec<usize> = (0..samples).collect();
+            strata.shuffle(rng);
+
+            for (point, stratum) in points.iter_mut().zip(strata) {
+                let level_idx = stratum * levels.len() / samples;
+                point.insert(name.clone(), levels[level_idx].clone());
+            }
+        }
+
+        points
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::collections::HashSet;
+
+    fn sweep(mode: &str, samples: Option<usize>, grid: Value) -> Sweep {
+        serde_json::from_value(json!({
+            "type": "SA",
+            "mode": mode,
+            "samples": samples,
+            "seed": 7,
+            "base": {
+                "initial_temperature": 1000.0,
+                "final_temperature": 1.0,
+                "cooling_rate": 0.95,
+                "mutate_func": "swap_indexes",
+            },
+            "grid": grid,
+        }))
+        .unwrap()
+    }
+
+    fn temperatures(params: &[AlgorithmParams]) -> Vec<(f64, f64)> {
+        params
+            .iter()
+            .map(|p| match p {
+                AlgorithmParams::SA {
+                    initial_temperature,
+                    cooling_rate,
+                    ..
+                } => (*initial_temperature, *cooling_rate),
+                _ => panic!("ожидается SA"),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn cartesian_covers_grid_and_drops_repeats() {
+        let grid =
+            json!({ "initial_temperature": [500.0, 500.0, 2000.0], "cooling_rate": [0.8, 0.9] });
+        let params = sweep("cartesian", None, grid).expand().unwrap();
+        assert_eq!(
+            temperatures(&params),
+            [(500.0, 0.8), (2000.0, 0.8), (500.0, 0.9), (2000.0, 0.9)]
+        );
+    }
+
+    #[test]
+    fn one_factor_varies_one_parameter_from_base() {
+        // Уровень, равный базовому значению, совпадает с базовой точкой
+        let grid = json!({ "initial_temperature": [500.0, 1000.0], "cooling_rate": [0.8] });
+        let params = sweep("one_factor", None, grid).expand().unwrap();
+        assert_eq!(
+            temperatures(&params),
+            [(1000.0, 0.95), (1000.0, 0.8), (500.0, 0.95)]
+        );
+    }
+
+    #[test]
+    fn one_factor_needs_base_value() {
+        let grid = json!({ "p": [0.1] });
+        assert!(sweep("one_factor", None, grid).expand().is_err());
+    }
+
+    #[test]
+    fn latin_hypercube_uses_every_stratum() {
+        let levels: Vec<f64> = (0..5).map(|i| 100.0 * (i + 1) as f64).collect();
+        let rates = [0.5, 0.6, 0.7, 0.8, 0.9];
+        let grid = json!({ "initial_temperature": levels, "cooling_rate": rates });
+        let params = sweep("latin_hypercube", Some(5), grid).expand().unwrap();
+
+        let points = temperatures(&params);
+        assert_eq!(points.len(), 5);
+        let mut seen: Vec<f64> = points.iter().map(|(t, _)| *t).collect();
+        seen.sort_by(f64::total_cmp);
+        assert_eq!(seen, levels);
+        let mut seen: Vec<f64> = points.iter().map(|(_, r)| *r).collect();
+        seen.sort_by(f64::total_cmp);
+        assert_eq!(seen, rates);
+    }
+
+    #[test]
+    fn latin_hypercube_resamples_repeated_points() {
+        let grid = json!({ "initial_temperature": [500.0, 2000.0], "cooling_rate": [0.8, 0.9] });
+
+        // В сетке 2x2 больше четырёх различных точек не набрать
+        for samples in [3, 4, 8] {
+            let params = sweep("latin_hypercube", Some(samples), grid.clone())
+                .expand()
+                .unwrap();
+            let points = temperatures(&params);
+            let distinct: HashSet<(u64, u64)> = points
+                .iter()
+                .map(|(t, r)| (t.to_bits(), r.to_bits()))
+                .collect();
+            assert_eq!(points.len(), samples.min(4));
+            assert_eq!(distinct.len(), points.len());
+        }
+    }
+
+    #[test]
+    fn latin_hypercube_is_reproducible() {
+        let grid = json!({ "initial_temperature": [250.0, 500.0, 1000.0, 2000.0], "cooling_rate": [0.8, 0.9, 0.95, 0.99] });
+        let first = sweep("latin_hypercube", Some(8), grid.clone())
+            .expand()
+            .unwrap();
+        let second = sweep("latin_hypercube", Some(8), grid).expand().unwrap();
+        assert_eq!(temperatures(&first), temperatures(&second));
+        assert!(sweep("latin_hypercube", None, json!({})).expand().is_err());
+    }
+}
@@ -1,3 +1,5 @@
 pub mod algorithm_params;
 pub mod algorithms;
+pub mod grid;
 pub mod run_algo;
+pub mod stopping;
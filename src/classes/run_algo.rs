@@ -4,15 +4,32 @@ use phd_cand_algorithms::builders::{
 };
 use phd_cand_algorithms::types::{Individual, Task};
 use rustc_hash::FxHashMap;
+use serde_json::Value;
+use std::collections::BTreeMap;
 
 use super::algorithm_params::AlgorithmParams;
+use super::stopping::{
+    StoppingCriterion, STOP_ERROR, STOP_FINISHED, STOP_INVALID_WEIGHT, STOP_NO_SOLUTION,
+    STOP_PANIC, STOP_SHUTDOWN, STOP_TIMEOUT,
+};
+use crate::instances::PreparedInstance;
+use crate::journal::config_hash;
+use crate::preprocessing::Pipelines;
+use crate::shutdown;
 use chrono::prelude::*;
-use std::cell::RefCell;
-use std::time::Instant;
+use std::any::Any;
+use std::cell::Cell;
+use std::mem;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::thread;
+use std::time::{Duration, Instant};
 
 use serde_json;
 
-use serde::{Serialize, Serializer};
+use serde::{Deserialize, Serialize, Serializer};
 
 fn as_json<T, S>(value: &T, serializer: S) -> Result<S::Ok, S::Error>
 where
@@ -23,77 +40,351 @@ where
     serializer.serialize_str(&json_string)
 }
 
+fn as_optional_json<T, S>(value: &Option<T>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    T: Serialize,
+    S: Serializer,
+{
+    match value {
+        Some(value) => as_json(value, serializer),
+        None => serializer.serialize_str(""),
+    }
+}
+
 #[derive(Clone, Serialize)]
 pub struct RunAlgoResultIteration {
-    iter_num: usize,
-    calc_time: u128,
-    results: FxHashMap<String, f64>,
-    path: Vec<usize>,
-    weight: f32,
+    pub iter_num: usize,
+    pub calc_time: u128,
+    pub results: FxHashMap<String, f64>,
+    pub path: Vec<usize>,
+    pub weight: f32,
+    /// Вычислений целевой функции за итерацию. Библиотека их не сообщает,
+    /// поэтому это оценка: размер популяции или число переданных в callback
+    /// решений, если их больше.
+    pub evaluations: usize,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum RunStatus {
+    #[default]
+    Ok,
+    /// Запуск прерван по тайм-ауту, сохранены итерации до прерывания
+    Timeout,
+    /// Алгоритм вернул ошибку
+    Error,
+    /// Алгоритм запаниковал
+    Panic,
+    /// Запуск прерван при остановке расчёта; такой результат не сохраняется,
+    /// и при продолжении расчёта запуск повторяется
+    Cancelled,
+}
+
+impl RunStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RunStatus::Ok => "OK",
+            RunStatus::Timeout => "TIMEOUT",
+            RunStatus::Error => "ERROR",
+            RunStatus::Panic => "PANIC",
+            RunStatus::Cancelled => "CANCELLED",
+        }
+    }
+}
+
+/// Чем закончился поток алгоритма: `Err` снаружи - паника, внутри - ошибка алгоритма
+type Outcome = Result<Result<(), String>, String>;
+
+/// Чем закончилось ожидание потока алгоритма
+enum Finished {
+    Done(Outcome),
+    /// Истёк тайм-аут запуска
+    TimedOut,
+    /// Истекло время на завершение после сигнала остановки
+    Cancelled,
+}
+
+/// Как часто наблюдатель проверяет тайм-аут и сигнал остановки
+const WATCHDOG_POLL: Duration = Duration::from_millis(100);
+
+/// Потоки алгоритмов, брошенные наблюдателем и ещё не завершившиеся
+static DETACHED: AtomicUsize = AtomicUsize::new(0);
+
+/// Сколько брошенных наблюдателем потоков алгоритмов ещё работает в фоне
+pub fn detached() -> usize {
+    DETACHED.load(Ordering::SeqCst)
+}
+
+/// Учитывает поток алгоритма, брошенный наблюдателем. Такой поток занимает
+/// процессор, пока алгоритм не вызовет callback, а зависший - до выхода из
+/// программы. Когда брошенных потоков становится столько же, сколько рабочих
+/// потоков расчёта, новые запуски не начинаются.
+fn detach(released: &AtomicBool) {
+    let count = DETACHED.fetch_add(1, Ordering::SeqCst) + 1;
+    if released.swap(true, Ordering::SeqCst) {
+        // Поток успел завершиться сам
+        DETACHED.fetch_sub(1, Ordering::SeqCst);
+        return;
+    }
+
+    if count >= rayon::current_num_threads() && !shutdown::requested() {
+        eprintln!(
+            "Зависших потоков алгоритмов: {}, они продолжают занимать процессор; новые запуски не начинаются",
+            count
+        );
+        shutdown::request();
+    }
 }
 
 #[derive(Clone, Serialize)]
 pub struct RunAlgoResult {
+    pub instance_id: String,
+    pub config_hash: String,
+    /// Номер повторения конфигурации на экземпляре, с нуля
+    pub repetition: usize,
+    pub seed: u64,
     #[serde(serialize_with = "as_json")]
-    pub tasks: Vec<Task>,
+    pub metadata: BTreeMap<String, Value>,
+    pub matrices_hash: String,
+    /// Матрицы экземпляра, только с `--embed-matrices`
+    #[serde(serialize_with = "as_optional_json")]
+    pub tasks: Option<Vec<Task>>,
+    #[serde(serialize_with = "as_json")]
+    pub criteria: Vec<String>,
+    #[serde(serialize_with = "as_json")]
+    pub preprocessing: Pipelines,
     #[serde(serialize_with = "as_json")]
     pub algo: AlgorithmParams,
     #[serde(serialize_with = "as_json")]
     pub iterations: Vec<RunAlgoResultIteration>,
+    pub status: RunStatus,
+    /// Сообщение ошибки или паники для статусов `ERROR` и `PANIC`
+    pub error: Option<String>,
+    /// Почему алгоритм остановился: сработавший критерий остановки или
+    /// `finished`, если алгоритм завершился сам
+    pub stop_reason: String,
     #[serde(serialize_with = "as_json")]
     pub calculation_time: i64,
 }
 
-pub fn run_algo(params: AlgorithmParams, tasks: Vec<Task>) -> Option<RunAlgoResult> {
-    const MAX_ATTEMPTS: usize = 10;
+impl RunAlgoResult {
+    /// Нечего сохранять в датасет: запуск завершился, не дав ни одной итерации
+    pub fn is_empty(&self) -> bool {
+        self.iterations.is_empty() && self.status == RunStatus::Ok
+    }
+}
+
+/// Состояние запуска, общее для потока алгоритма и наблюдателя
+#[derive(Default)]
+struct Progress {
+    iterations: Vec<RunAlgoResultIteration>,
+    stop_reason: Option<String>,
+}
+
+impl Progress {
+    /// Запоминает причину остановки; результат возвращается из callback
+    fn stop(&mut self, reason: &str) -> bool {
+        self.stop_reason = Some(reason.to_string());
+        false
+    }
+}
+
+/// Сборщики `phd_cand_algorithms` пока не принимают зерно генератора, поэтому
+/// `seed` только записывается в результат вместе с номером повторения.
+///
+/// Алгоритм работает в отдельном потоке. По истечении `timeout` callback
+/// возвращает `false`, а если алгоритм завис внутри итерации и callback не
+/// вызывается, запуск прерывает наблюдатель: результат сохраняется с уже
+/// посчитанными итерациями, поток алгоритма остаётся доработать в фоне и
+/// остановится на следующем вызове callback (см. `detach`). Так же запуск
+/// прерывается, когда истекает время на завершение после сигнала остановки.
+///
+/// Ошибка или паника алгоритма не прерывает расчёт: запуск сохраняется со
+/// статусом `ERROR` или `PANIC`, сообщением и итерациями до сбоя.
+pub fn run_algo(
+    params: AlgorithmParams,
+    repetition: usize,
+    seed: u64,
+    stopping: &StoppingCriterion,
+    timeout: Option<Duration>,
+    prepared: &PreparedInstance,
+) -> RunAlgoResult {
+    let progress: Arc<Mutex<Progress>> = Arc::new(Mutex::new(Progress::default()));
+    let cancelled = Arc::new(AtomicBool::new(false));
+    let released = Arc::new(AtomicBool::new(false));
+    let (done_sender, done_receiver) = mpsc::channel();
+
+    let worker = {
+        let progress = progress.clone();
+        let cancelled = cancelled.clone();
+        let stopping = stopping.clone();
+        let params = params.clone();
+        let tasks = prepared.tasks.clone();
+        let released = released.clone();
+        move || {
+            let outcome: Outcome = panic::catch_unwind(AssertUnwindSafe(|| {
+                run_builder(params, tasks, &stopping, timeout, &progress, &cancelled)
+            }))
+            .map_err(|payload| panic_message(payload.as_ref()));
+            // Наблюдатель уже бросил поток и учёл его в `DETACHED`
+            if released.swap(true, Ordering::SeqCst) {
+                DETACHED.fetch_sub(1, Ordering::SeqCst);
+            }
+            let _ = done_sender.send(outcome);
+        }
+    };
+
+    let time_start = Local::now();
+    let deadline = timeout.map(|timeout| Instant::now() + timeout);
+    let finished = match thread::Builder::new().spawn(worker) {
+        Err(e) => Finished::Done(Ok(Err(format!(
+            "не удалось запустить поток алгоритма: {}",
+            e
+        )))),
+        Ok(_) => loop {
+            match done_receiver.recv_timeout(WATCHDOG_POLL) {
+                Ok(outcome) => break Finished::Done(outcome),
+                Err(RecvTimeoutError::Disconnected) => {
+                    break Finished::Done(Err(
+                        "поток алгоритма завершился без результата".to_string()
+                    ))
+                }
+                Err(RecvTimeoutError::Timeout) => {}
+            }
+            if shutdown::cancelled() {
+                break Finished::Cancelled;
+            }
+            if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+                break Finished::TimedOut;
+            }
+        },
+    };
+
+    if matches!(finished, Finished::TimedOut | Finished::Cancelled) {
+        detach(&released);
+    }
+
+    // Под блокировкой: поток алгоритма, если он ещё работает, больше ничего не запишет
+    let progress = {
+        let mut progress = progress.lock().unwrap_or_else(PoisonError::into_inner);
+        cancelled.store(true, Ordering::SeqCst);
+        mem::take(&mut *progress)
+    };
+    let stop_reason = match &finished {
+        Finished::Done(Ok(Ok(()))) => progress
+            .stop_reason
+            .unwrap_or_else(|| STOP_FINISHED.to_string()),
+        Finished::Done(Ok(Err(_))) => STOP_ERROR.to_string(),
+        Finished::Done(Err(_)) => STOP_PANIC.to_string(),
+        Finished::TimedOut => STOP_TIMEOUT.to_string(),
+        Finished::Cancelled => STOP_SHUTDOWN.to_string(),
+    };
+    let (status, error) = match finished {
+        Finished::Done(Ok(Ok(()))) => match stop_reason.as_str() {
+            STOP_TIMEOUT => (RunStatus::Timeout, None),
+            STOP_SHUTDOWN => (RunStatus::Cancelled, None),
+            _ => (RunStatus::Ok, None),
+        },
+        Finished::Done(Ok(Err(e))) => (RunStatus::Error, Some(e)),
+        Finished::Done(Err(e)) => (RunStatus::Panic, Some(e)),
+        Finished::TimedOut => (RunStatus::Timeout, None),
+        Finished::Cancelled => (RunStatus::Cancelled, None),
+    };
+
+    let calculation_time = Local::now()
+        .signed_duration_since(time_start)
+        .num_milliseconds();
+    RunAlgoResult {
+        instance_id: prepared.instance.id.clone(),
+        config_hash: config_hash(&params),
+        repetition,
+        seed,
+        metadata: prepared.instance.metadata.clone(),
+        criteria: prepared.tasks.iter().map(|task| task.name.clone()).collect(),
+        preprocessing: prepared.preprocessing.clone(),
+        matrices_hash: prepared.matrices_hash.clone(),
+        tasks: prepared.embed_matrices.then(|| prepared.tasks.clone()),
+        calculation_time,
+        algo: params,
+        iterations: progress.iterations,
+        status,
+        error,
+        stop_reason,
+    }
+}
+
+fn panic_message(payload: &(dyn Any + Send)) -> String {
+    payload
+        .downcast_ref::<&str>()
+        .map(|message| message.to_string())
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "паника без сообщения".to_string())
+}
+
+/// Запускает алгоритм; ошибка алгоритма возвращается текстом
+fn run_builder(
+    params: AlgorithmParams,
+    tasks: Vec<Task>,
+    stopping: &StoppingCriterion,
+    timeout: Option<Duration>,
+    progress: &Mutex<Progress>,
+    cancelled: &AtomicBool,
+) -> Result<(), String> {
     const INVALID_VALUE: f32 = -999.0;
 
-    let iterations: RefCell<Vec<RunAlgoResultIteration>> = RefCell::new(Vec::with_capacity(60));
-    let calculation_start = RefCell::new(Instant::now());
+    let run_start = Instant::now();
+    let evaluations_per_iteration = params.evaluations_per_iteration();
+    let calculation_start = Cell::new(Instant::now());
     let callback_fn = |individuals: Vec<Individual>| {
+        let mut progress = progress.lock().unwrap_or_else(PoisonError::into_inner);
+        if cancelled.load(Ordering::SeqCst) {
+            return false;
+        }
+
         let best_solution = match individuals.first() {
             Some(s) => s,
-            None => return false,
+            None => return progress.stop(STOP_NO_SOLUTION),
         };
 
-        let mut iters = iterations.borrow_mut();
-
         match best_solution.weight {
             Some(weight) => {
                 let result = RunAlgoResultIteration {
-                    iter_num: iters.len() + 1,
-                    calc_time: calculation_start.borrow().elapsed().as_millis(),
+                    iter_num: progress.iterations.len() + 1,
+                    calc_time: calculation_start.get().elapsed().as_millis(),
                     path: best_solution.value.clone(),
                     results: best_solution.results.clone(),
                     weight,
+                    evaluations: individuals.len().max(evaluations_per_iteration),
                 };
-                iters.push(result);
+                progress.iterations.push(result);
             }
-            None => return false,
+            None => return progress.stop(STOP_NO_SOLUTION),
         }
 
-        let (max_el_idx, w) = iters
+        let best_weight = progress
+            .iterations
             .iter()
-            .enumerate()
-            .fold(None, |acc, (idx, el)| match acc {
-                Some((_, weight_max)) if el.weight > weight_max => {
-                    Some((idx, el.weight))
-                },
-                None => Some((idx, el.weight)),
-                _ => acc
-            })
-            .unwrap_or((0, INVALID_VALUE));
-
-        if w == INVALID_VALUE || iters.len() - max_el_idx > MAX_ATTEMPTS {
-            return false;
+            .map(|el| el.weight)
+            .reduce(f32::max)
+            .unwrap_or(INVALID_VALUE);
+        if best_weight == INVALID_VALUE {
+            return progress.stop(STOP_INVALID_WEIGHT);
+        }
+        let elapsed = run_start.elapsed();
+        if timeout.is_some_and(|timeout| elapsed >= timeout) {
+            return progress.stop(STOP_TIMEOUT);
+        }
+        if shutdown::cancelled() {
+            return progress.stop(STOP_SHUTDOWN);
+        }
+        if let Some(reason) = stopping.check(&progress.iterations, elapsed) {
+            return progress.stop(&reason);
         }
 
-        calculation_start.replace(Instant::now());
-        return true;
+        calculation_start.set(Instant::now());
+        true
     };
 
-    let tasks_clone = tasks.clone();
-    let time_start = Local::now();
     let result = match params {
         AlgorithmParams::AC {
             alpha,
@@ -102,7 +393,7 @@ pub fn run_algo(params: AlgorithmParams, tasks: Vec<Task>) -> Option<RunAlgoResu
             p,
             actors_count,
         } => {
-            let algo = AntColonyAlgorithmBuilder::new(tasks_clone)
+            let algo = AntColonyAlgorithmBuilder::new(tasks)
                 .actors_count(actors_count)
                 .alpha(alpha)
                 .beta(beta)
@@ -117,7 +408,7 @@ pub fn run_algo(params: AlgorithmParams, tasks: Vec<Task>) -> Option<RunAlgoResu
             research_func,
             actors_count,
         } => {
-            let algo = BeeColonyAlgorithmBuilder::new(tasks_clone)
+            let algo = BeeColonyAlgorithmBuilder::new(tasks)
                 .workers_part(workers_part)
                 .solutions_count(1)
                 .research_func_str(research_func)
@@ -131,7 +422,7 @@ pub fn run_algo(params: AlgorithmParams, tasks: Vec<Task>) -> Option<RunAlgoResu
             mutate_func,
             actors_count,
         } => {
-            let algo = GeneticAlgorithmBuilder::new(tasks_clone)
+            let algo = GeneticAlgorithmBuilder::new(tasks)
                 .p_mutation(p_mutation)
                 .select_func_str(select_func)
                 .mutate_func_str(mutate_func)
@@ -145,7 +436,7 @@ pub fn run_algo(params: AlgorithmParams, tasks: Vec<Task>) -> Option<RunAlgoResu
             cooling_rate,
             mutate_func,
         } => {
-            let algo = SimulatedAnnealingBuilder::new(tasks_clone)
+            let algo = SimulatedAnnealingBuilder::new(tasks)
                 .initial_temperature(initial_temperature)
                 .final_temperature(final_temperature)
                 .cooling_rate(cooling_rate)
@@ -155,17 +446,5 @@ pub fn run_algo(params: AlgorithmParams, tasks: Vec<Task>) -> Option<RunAlgoResu
         }
     };
 
-    if let Ok(_) = result {
-        let calculation_time = Local::now()
-            .signed_duration_since(time_start)
-            .num_milliseconds();
-        Some(RunAlgoResult {
-            tasks,
-            calculation_time,
-            algo: params,
-            iterations: iterations.into_inner(),
-        })
-    } else {
-        None
-    }
+    result.map(|_| ()).map_err(|e| format!("{:?}", e))
 }
@@ -0,0 +1,250 @@
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+use super::run_algo::RunAlgoResultIteration;
+
+/// Алгоритм остановился сам, не дожидаясь критерия
+pub const STOP_FINISHED: &str = "finished";
+/// Алгоритм не вернул решения или вернул решение без веса
+pub const STOP_NO_SOLUTION: &str = "no_solution";
+/// Запуск длился дольше тайм-аута эксперимента
+pub const STOP_TIMEOUT: &str = "timeout";
+/// Запуск прерван при остановке расчёта по сигналу
+pub const STOP_SHUTDOWN: &str = "shutdown";
+/// Алгоритм вернул ошибку
+pub const STOP_ERROR: &str = "error";
+/// Алгоритм запаниковал
+pub const STOP_PANIC: &str = "panic";
+/// Лучший вес равен служебному значению некорректного решения
+pub const STOP_INVALID_WEIGHT: &str = "invalid_weight";
+
+/// Правило остановки алгоритма, проверяется после каждой итерации.
+/// Больший вес решения считается лучшим.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum StoppingCriterion {
+    /// Не больше `count` итераций
+    MaxIterations { count: usize },
+    /// Не больше `count` вычислений целевой функции - общий бюджет для
+    /// сравнения алгоритмов с разным числом вычислений за итерацию
+    MaxEvaluations { count: usize },
+    /// Не дольше `seconds` секунд от начала запуска
+    TimeBudget { seconds: f64 },
+    /// Лучший вес не улучшался больше чем на `tolerance` (доля от модуля
+    /// лучшего веса) дольше `window` итераций
+    Stagnation {
+        window: usize,
+        #[serde(default)]
+        tolerance: f64,
+    },
+    /// Лучший вес достиг `weight`
+    TargetWeight { weight: f32 },
+    /// Сработал хотя бы один из критериев
+    Any { criteria: Vec<StoppingCriterion> },
+    /// Сработали все критерии
+    All { criteria: Vec<StoppingCriterion> },
+}
+
+/// Прежнее жёстко заданное правило: 10 итераций без улучшения
+impl Default for StoppingCriterion {
+    fn default() -> Self {
+        Self::Stagnation {
+            window: 10,
+            tolerance: 0.0,
+        }
+    }
+}
+
+impl StoppingCriterion {
+    /// Причина остановки или `None`, если алгоритм нужно продолжать
+    pub fn check(
+        &self,
+        iterations: &[RunAlgoResultIteration],
+        elapsed: Duration,
+    ) -> Option<String> {
+        match self {
+            Self::MaxIterations { count } => {
+                (iterations.len() >= *count).then(|| "max_iterations".to_string())
+            }
+            Self::MaxEvaluations { count } => {
+                (evaluations(iterations) >= *count).then(|| "max_evaluations".to_string())
+            }
+            Self::TimeBudget { seconds } => {
+                (elapsed.as_secs_f64() >= *seconds).then(|| "time_budget".to_string())
+            }
+            Self::Stagnation { window, tolerance } => {
+                (iterations.len() - last_improvement(iterations, *tolerance) > *window)
+                    .then(|| "stagnation".to_string())
+            }
+            Self::TargetWeight { weight } => iterations
+                .iter()
+                .any(|iteration| iteration.weight >= *weight)
+                .then(|| "target_weight".to_string()),
+            Self::Any { criteria } => criteria
+                .iter()
+                .find_map(|criterion| criterion.check(iterations, elapsed)),
+            Self::All { criteria } => criteria
+                .iter()
+                .map(|criterion| criterion.check(iterations, elapsed))
+                .collect::<Option<Vec<String>>>()
+                .map(|reasons| reasons.join("+")),
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        match self {
+            Self::MaxIterations { count } if *count == 0 => {
+                Err("max_iterations: count должен быть > 0".to_string())
+            }
+            Self::MaxEvaluations { count } if *count == 0 => {
+                Err("max_evaluations: count должен быть > 0".to_string())
+            }
+            Self::TimeBudget { seconds } if !(seconds.is_finite() && *seconds > 0.0) => Err(
+                format!("time_budget: seconds должно быть > 0, получено {}", seconds),
+            ),
+            Self::Stagnation { window, .. } if *window == 0 => {
+                Err("stagnation: window должно быть > 0".to_string())
+            }
+            Self::Stagnation { tolerance, .. } if !(tolerance.is_finite() && *tolerance >= 0.0) => {
+                Err(format!(
+                    "stagnation: tolerance должно быть >= 0, получено {}",
+                    tolerance
+                ))
+            }
+            Self::TargetWeight { weight } if !weight.is_finite() => Err(format!(
+                "target_weight: weight должен быть конечным, получено {}",
+                weight
+            )),
+            Self::Any { criteria } | Self::All { criteria } => {
+                if criteria.is_empty() {
+                    return Err("any/all: список критериев пуст".to_string());
+                }
+                criteria.iter().try_for_each(StoppingCriterion::validate)
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Вычислений целевой функции с начала запуска
+pub fn evaluations(iterations: &[RunAlgoResultIteration]) -> usize {
+    iterations
+        .iter()
+        .map(|iteration| iteration.evaluations)
+        .sum()
+}
+
+/// Индекс итерации, на которой лучший вес последний раз вырос больше чем на
+/// `tolerance` от своего модуля
+fn last_improvement(iterations: &[RunAlgoResultIteration], tolerance: f64) -> usize {
+    let mut best: Option<f64> = None;
+    let mut best_idx = 0;
+    for (idx, iteration) in iterations.iter().enumerate() {
+        let weight = iteration.weight as f64;
+        match best {
+            Some(best_weight) if weight <= best_weight + tolerance * best_weight.abs() => {}
+            _ => {
+                best = Some(weight);
+                best_idx = idx;
+            }
+        }
+    }
+    best_idx
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn iterations(weights: &[f32]) -> Vec<RunAlgoResultIteration> {
+        weights
+            .iter()
+            .enumerate()
+            .map(|(iter_num, &weight)| RunAlgoResultIteration {
+                iter_num,
+                calc_time: 0,
+                results: Default::default(),
+                path: vec![],
+                weight,
+                evaluations: 10,
+            })
+            .collect()
+    }
+
+    fn check(criterion: &StoppingCriterion, weights: &[f32]) -> Option<String> {
+        criterion.check(&iterations(weights), Duration::from_secs(1))
+    }
+
+    #[test]
+    fn stagnation_counts_iterations_after_last_improvement() {
+        let criterion = StoppingCriterion::Stagnation {
+            window: 3,
+            tolerance: 0.0,
+        };
+        assert_eq!(check(&criterion, &[]), None);
+        assert_eq!(check(&criterion, &[1.0, 1.0, 1.0]), None);
+        assert_eq!(
+            check(&criterion, &[1.0, 1.0, 1.0, 1.0]),
+            Some("stagnation".to_string())
+        );
+        assert_eq!(check(&criterion, &[1.0, 2.0, 2.0, 0.5]), None);
+        assert_eq!(
+            check(&criterion, &[1.0, 2.0, 2.0, 0.5, 2.0]),
+            Some("stagnation".to_string())
+        );
+    }
+
+    #[test]
+    fn stagnation_ignores_improvements_within_tolerance() {
+        let weights = [10.0, 10.5, 10.8, 10.9];
+        let strict = StoppingCriterion::Stagnation {
+            window: 3,
+            tolerance: 0.0,
+        };
+        let tolerant = StoppingCriterion::Stagnation {
+            window: 3,
+            tolerance: 0.1,
+        };
+        assert_eq!(check(&strict, &weights), None);
+        assert_eq!(check(&tolerant, &weights), Some("stagnation".to_string()));
+        // Рост больше чем на 10% сбрасывает окно
+        assert_eq!(check(&tolerant, &[10.0, 10.5, 11.5, 11.6]), None);
+    }
+
+    #[test]
+    fn any_and_all_combine_reasons() {
+        let criteria = vec![
+            StoppingCriterion::MaxIterations { count: 2 },
+            StoppingCriterion::MaxEvaluations { count: 30 },
+        ];
+        let any = StoppingCriterion::Any {
+            criteria: criteria.clone(),
+        };
+        let all = StoppingCriterion::All { criteria };
+
+        assert_eq!(check(&any, &[1.0]), None);
+        assert_eq!(check(&any, &[1.0, 2.0]), Some("max_iterations".to_string()));
+        assert_eq!(check(&all, &[1.0, 2.0]), None);
+        assert_eq!(
+            check(&all, &[1.0, 2.0, 3.0]),
+            Some("max_iterations+max_evaluations".to_string())
+        );
+    }
+
+    #[test]
+    fn validate_checks_nested_criteria() {
+        let nested = StoppingCriterion::Any {
+            criteria: vec![StoppingCriterion::All {
+                criteria: vec![StoppingCriterion::Stagnation {
+                    window: 5,
+                    tolerance: -0.1,
+                }],
+            }],
+        };
+        assert!(nested.validate().is_err());
+        assert!(StoppingCriterion::All { criteria: vec![] }
+            .validate()
+            .is_err());
+        assert!(StoppingCriterion::default().validate().is_ok());
+    }
+}
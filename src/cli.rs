@@ -0,0 +1,216 @@
+use clap::{Args, Parser, Subcommand};
+use std::path::PathBuf;
+
+use crate::compression::Compression;
+use crate::generator::{ImportanceDist, Layout, SpeedProfile};
+use crate::sinks::SinkKind;
+use crate::validation::ValidationPolicy;
+
+pub const MATRICES_DIR: &str = "matrices";
+pub const RUNS_DIR: &str = "runs";
+pub const ROWS_PER_PART_DEFAULT: usize = 100;
+pub const GRACE_PERIOD_DEFAULT: u64 = 50;
+pub const MATRICES_COUNT_TARGET_DEFAULT: usize = 100;
+
+#[derive(Parser)]
+#[command(version, about, args_conflicts_with_subcommands = true)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    /// Параметры для запуска без подкоманды (эквивалентно `run`)
+    #[command(flatten)]
+    pub run: RunArgs,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// Запустить новый расчёт (или продолжить расчёт в --run-dir, если он там уже есть)
+    Run(RunArgs),
+    /// Продолжить расчёт из указанного каталога
+    Resume(ResumeArgs),
+    /// Показать, что будет посчитано, ничего не запуская
+    Plan(RunArgs),
+    /// Вывести сводку по готовому датасету
+    Summarize(SummarizeArgs),
+    /// Проверить конфиг эксперимента и входные данные
+    Validate(RunArgs),
+    /// Сгенерировать синтетические экземпляры
+    Generate(GenerateArgs),
+    /// Вывести агрегированную таблицу из SQLite-базы расчёта
+    Query(QueryArgs),
+}
+
+#[derive(Args, Clone)]
+pub struct RunArgs {
+    /// Каталог с матрицами
+    #[arg(long, default_value = MATRICES_DIR)]
+    pub input_dir: PathBuf,
+
+    /// Манифест экземпляров (.json или .csv) вместо поиска матриц в input-dir
+    #[arg(long)]
+    pub manifest: Option<PathBuf>,
+
+    /// Каталог, в котором создаются каталоги расчётов
+    #[arg(long, default_value = RUNS_DIR)]
+    pub output_dir: PathBuf,
+
+    /// Каталог расчёта (по умолчанию `<output-dir>/run_<дата>_<время>`)
+    #[arg(long)]
+    pub run_dir: Option<PathBuf>,
+
+    /// Конфиг эксперимента (.toml или .json)
+    #[arg(long, env = "EXPERIMENT_CONFIG")]
+    pub config: Option<PathBuf>,
+
+    /// Количество потоков расчёта (по умолчанию - по числу ядер)
+    #[arg(long)]
+    pub threads: Option<usize>,
+
+    /// Что делать с некорректными или неполными экземплярами
+    #[arg(long, value_enum, default_value_t = ValidationPolicy::Fail)]
+    pub on_invalid: ValidationPolicy,
+
+    /// Приёмники результатов через запятую
+    #[arg(
+        long,
+        env = "RESULT_SINKS",
+        value_enum,
+        value_delimiter = ',',
+        default_value = "csv"
+    )]
+    pub sinks: Vec<SinkKind>,
+
+    #[command(flatten)]
+    pub execution: ExecutionArgs,
+
+    /// Добавить в iterations.csv маршрут лучшего решения
+    #[arg(long)]
+    pub iterations_path: bool,
+
+    /// Сжатие датасетов, таблицы итераций и лога: файлы получают расширение
+    /// `.gz` или `.zst`
+    #[arg(long, env = "COMPRESSION", value_enum, default_value_t = Compression::None)]
+    pub compression: Compression,
+
+    /// Сохранять матрицы экземпляра в каждой строке датасета (как раньше);
+    /// по умолчанию строка ссылается на экземпляр по ID и хешу матриц
+    #[arg(long)]
+    pub embed_matrices: bool,
+
+    /// Сколько экземпляров обработать (каждый экземпляр - набор матриц критериев)
+    #[arg(long, env = "MATRICES_COUNT", default_value_t = MATRICES_COUNT_TARGET_DEFAULT)]
+    pub limit: usize,
+}
+
+#[derive(Args, Clone)]
+pub struct ResumeArgs {
+    /// Каталог расчёта с run.json
+    pub run_dir: PathBuf,
+
+    /// Количество потоков расчёта (по умолчанию - по числу ядер)
+    #[arg(long)]
+    pub threads: Option<usize>,
+
+    /// Что делать с экземплярами, которые стали некорректными с начала расчёта
+    #[arg(long, value_enum, default_value_t = ValidationPolicy::Fail)]
+    pub on_invalid: ValidationPolicy,
+
+    #[command(flatten)]
+    pub execution: ExecutionArgs,
+}
+
+// Настройки выполнения, которые не сохраняются в run.json и могут меняться
+// при продолжении расчёта. Не doc-комментарий: clap сделал бы его описанием
+// программы в `--help`, потому что структура встроена в аргументы без подкоманды.
+#[derive(Args, Clone)]
+pub struct ExecutionArgs {
+    /// Сколько строк копить перед записью очередного part-файла Parquet
+    #[arg(long, default_value_t = ROWS_PER_PART_DEFAULT)]
+    pub rows_per_part: usize,
+
+    /// Сколько секунд после SIGINT/SIGTERM ждать завершения текущих запусков,
+    /// прежде чем прервать их (меньше stop_grace_period в docker-compose)
+    #[arg(long, env = "SHUTDOWN_GRACE_PERIOD", default_value_t = GRACE_PERIOD_DEFAULT)]
+    pub grace_period: u64,
+}
+
+#[derive(Args, Clone)]
+pub struct SummarizeArgs {
+    /// CSV-файл датасета (`.csv`, `.csv.gz` или `.csv.zst`)
+    pub dataset: PathBuf,
+}
+
+#[derive(Args, Clone)]
+pub struct QueryArgs {
+    /// Файл results.sqlite или каталог расчёта с ним
+    pub database: PathBuf,
+
+    /// Произвольный SQL-запрос вместо сводки по семействам алгоритмов и размерам
+    #[arg(long)]
+    pub sql: Option<String>,
+}
+
+#[derive(Args, Clone)]
+pub struct GenerateArgs {
+    /// Каталог для сгенерированных матриц
+    #[arg(long, default_value = MATRICES_DIR)]
+    pub output_dir: PathBuf,
+
+    /// Размеры экземпляров через запятую
+    #[arg(long, value_delimiter = ',', default_value = "10,20,50,100,200,500")]
+    pub sizes: Vec<usize>,
+
+    /// Количество экземпляров каждого размера
+    #[arg(long, default_value_t = 10)]
+    pub instances: usize,
+
+    /// Базовое зерно генератора
+    #[arg(long, default_value_t = 0)]
+    pub seed: u64,
+
+    #[arg(long, value_enum, default_value_t = Layout::Uniform)]
+    pub layout: Layout,
+
+    /// Сторона квадратной области, в которой размещаются точки
+    #[arg(long, default_value_t = 1000.0)]
+    pub area: f64,
+
+    /// Количество кластеров для --layout clustered
+    #[arg(long, default_value_t = 5)]
+    pub clusters: usize,
+
+    /// Разброс точек вокруг центра кластера, доля от --area
+    #[arg(long, default_value_t = 0.05)]
+    pub cluster_spread: f64,
+
+    #[arg(long, value_enum, default_value_t = SpeedProfile::Constant)]
+    pub speed_profile: SpeedProfile,
+
+    /// Базовая скорость (единиц расстояния на единицу времени)
+    #[arg(long, default_value_t = 1.0)]
+    pub speed: f64,
+
+    /// Сигма логнормального шума времени переезда
+    #[arg(long, default_value_t = 0.1)]
+    pub time_noise: f64,
+
+    #[arg(long, value_enum, default_value_t = ImportanceDist::Uniform)]
+    pub importance_dist: ImportanceDist,
+
+    /// Границы для --importance-dist uniform
+    #[arg(long, default_value_t = 1.0)]
+    pub importance_low: f64,
+    #[arg(long, default_value_t = 10.0)]
+    pub importance_high: f64,
+
+    /// Параметры для --importance-dist normal (отрицательные значения обрезаются до 0)
+    #[arg(long, default_value_t = 5.0)]
+    pub importance_mean: f64,
+    #[arg(long, default_value_t = 2.0)]
+    pub importance_std: f64,
+
+    /// Интенсивность для --importance-dist exponential
+    #[arg(long, default_value_t = 0.2)]
+    pub importance_rate: f64,
+}
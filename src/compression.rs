@@ -0,0 +1,68 @@
+use clap::ValueEnum;
+use flate2::read::MultiGzDecoder;
+use flate2::write::GzEncoder;
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{self, BufReader, Read, Write};
+use std::path::Path;
+
+const ZSTD_LEVEL: i32 = 3;
+
+/// Сжатие текстовых файлов расчёта (датасетов, таблицы итераций, лога).
+/// Каждая запись сжимается отдельным фреймом: склеенные члены gzip и фреймы
+/// zstd читаются как один поток, а обрыв записи при аварийном завершении
+/// портит только последний фрейм, который можно отрезать.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum Compression {
+    #[default]
+    None,
+    /// `.gz`
+    Gzip,
+    /// `.zst`
+    Zstd,
+}
+
+impl Compression {
+    /// Сжатие файла по его расширению
+    pub fn of(path: &Path) -> Self {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("gz") => Self::Gzip,
+            Some("zst") => Self::Zstd,
+            _ => Self::None,
+        }
+    }
+
+    /// Имя файла с расширением сжатия: `dataset.csv` -> `dataset.csv.zst`
+    pub fn file_name(self, name: &str) -> String {
+        match self {
+            Self::None => name.to_string(),
+            Self::Gzip => format!("{}.gz", name),
+            Self::Zstd => format!("{}.zst", name),
+        }
+    }
+
+    /// Сжимает запись в самостоятельный фрейм
+    pub fn compress(self, record: &[u8]) -> io::Result<Vec<u8>> {
+        match self {
+            Self::None => Ok(record.to_vec()),
+            Self::Gzip => {
+                let mut encoder = GzEncoder::new(vec![], flate2::Compression::default());
+                encoder.write_all(record)?;
+                encoder.finish()
+            }
+            Self::Zstd => zstd::encode_all(record, ZSTD_LEVEL),
+        }
+    }
+
+    /// Распакованное содержимое файла; сжатие определяется по расширению
+    pub fn reader(path: &Path) -> io::Result<Box<dyn Read>> {
+        let file = BufReader::new(File::open(path)?);
+
+        Ok(match Self::of(path) {
+            Self::None => Box::new(file),
+            Self::Gzip => Box::new(MultiGzDecoder::new(file)),
+            Self::Zstd => Box::new(zstd::Decoder::with_buffer(file)?),
+        })
+    }
+}
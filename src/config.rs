@@ -0,0 +1,196 @@
+use serde::{Deserialize, Serialize};
+use std::{collections::HashSet, fs, path::Path};
+
+use crate::classes::algorithm_params::AlgorithmParams;
+use crate::classes::algorithms::ALGORITHMS;
+use crate::classes::grid::Sweep;
+use crate::classes::stopping::StoppingCriterion;
+use crate::instances::{default_criteria, Criterion};
+use crate::journal::{config_hash, JobKey};
+use crate::preprocessing::{default_pipelines, Pipelines, PreprocessStep, DEFAULT_KEY};
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ExperimentConfig {
+    #[serde(default = "default_criteria")]
+    pub criteria: Vec<Criterion>,
+    #[serde(default = "default_pipelines")]
+    pub preprocessing: Pipelines,
+    #[serde(default)]
+    pub algorithms: Vec<AlgorithmParams>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub sweeps: Vec<Sweep>,
+    /// Сколько раз запускать каждую конфигурацию на каждом экземпляре
+    #[serde(default = "default_repetitions")]
+    pub repetitions: usize,
+    /// Базовое зерно, из которого выводятся зёрна отдельных запусков
+    #[serde(default)]
+    pub seed: u64,
+    /// Когда останавливать алгоритм; по умолчанию - 10 итераций без улучшения
+    #[serde(default)]
+    pub stopping: StoppingCriterion,
+    /// Предельное время одного запуска, секунд; запуск сверх него прерывается
+    /// и сохраняется со статусом `TIMEOUT`
+    #[serde(default)]
+    pub timeout: Option<f64>,
+}
+
+fn default_repetitions() -> usize {
+    1
+}
+
+impl Default for ExperimentConfig {
+    fn default() -> Self {
+        Self {
+            criteria: default_criteria(),
+            preprocessing: default_pipelines(),
+            algorithms: ALGORITHMS.to_vec(),
+            sweeps: vec![],
+            repetitions: default_repetitions(),
+            seed: 0,
+            stopping: StoppingCriterion::default(),
+            timeout: None,
+        }
+    }
+}
+
+impl ExperimentConfig {
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let content = fs::read_to_string(path)
+            .map_err(|e| format!("Не удалось прочитать {:?}: {}", path, e))?;
+
+        let config: Self = match path.extension().and_then(|ext| ext.to_str()) {
+            Some("toml") => toml::from_str(&content).map_err(|e| e.to_string()),
+            Some("json") => serde_json::from_str(&content).map_err(|e| e.to_string()),
+            _ => Err("ожидается файл .toml или .json".to_string()),
+        }
+        .map_err(|e| format!("Некорректный конфиг {:?}: {}", path, e))?;
+
+        Ok(config)
+    }
+
+    /// Все запуски экземпляра: каждая конфигурация `repetitions` раз
+    pub fn jobs<'a>(
+        &'a self,
+        instance_id: &'a str,
+    ) -> impl Iterator<Item = (JobKey, &'a AlgorithmParams)> + 'a {
+        self.algorithms.iter().flat_map(move |params| {
+            (0..self.repetitions)
+                .map(move |repetition| (JobKey::new(instance_id, params, repetition), params))
+        })
+    }
+
+    /// Разворачивает `sweeps` в явный список конфигураций после `algorithms`.
+    /// Совпадающие конфигурации (с одинаковым `config_hash`) остаются в одном
+    /// экземпляре - первом по порядку, иначе один и тот же запуск планируется дважды.
+    pub fn resolve(mut self) -> Result<Self, String> {
+        for (idx, sweep) in self.sweeps.iter().enumerate() {
+            let expanded = sweep
+                .expand()
+                .map_err(|e| format!("sweeps[{}] ({}): {}", idx, sweep.algorithm, e))?;
+            self.algorithms.extend(expanded);
+        }
+        self.sweeps.clear();
+
+        let mut hashes: HashSet<String> = HashSet::new();
+        self.algorithms
+            .retain(|params| hashes.insert(config_hash(params)));
+
+        Ok(self)
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if self.algorithms.is_empty() {
+            return Err("algorithms: список пуст".to_string());
+        }
+        if self.criteria.is_empty() {
+            return Err("criteria: список пуст".to_string());
+        }
+        if self.repetitions == 0 {
+            return Err("repetitions: должно быть не меньше 1".to_string());
+        }
+        self.stopping
+            .validate()
+            .map_err(|e| format!("stopping: {}", e))?;
+        if let Some(timeout) = self.timeout {
+            if !(timeout.is_finite() && timeout > 0.0) {
+                return Err(format!("timeout: должен быть > 0, получено {}", timeout));
+            }
+        }
+
+        let mut errors: Vec<String> = vec![];
+        for (idx, criterion) in self.criteria.iter().enumerate() {
+            let name = &criterion.name;
+            if name.is_empty()
+                || !name
+                    .chars()
+                    .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit())
+            {
+                errors.push(format!(
+                    "criteria[{}]: имя {:?} должно состоять из строчных латинских букв и цифр",
+                    idx, name
+                ));
+            }
+            if self.criteria[..idx].iter().any(|c| &c.name == name) {
+                errors.push(format!("criteria[{}]: повторяющееся имя {:?}", idx, name));
+            }
+        }
+
+        for (key, steps) in &self.preprocessing {
+            if key != DEFAULT_KEY && !self.criteria.iter().any(|c| &c.name == key) {
+                errors.push(format!(
+                    "preprocessing.{}: нет такого критерия, ожидается {:?} или имя критерия",
+                    key, DEFAULT_KEY
+                ));
+            }
+            for step in steps {
+                match step {
+                    PreprocessStep::Decimals { digits } if !(0..=15).contains(digits) => errors
+                        .push(format!(
+                            "preprocessing.{}: digits для decimals должно быть в 0..=15, получено {}",
+                            key, digits
+                        )),
+                    PreprocessStep::Scale { factor } if !factor.is_finite() => errors.push(
+                        format!("preprocessing.{}: factor для scale должен быть конечным", key),
+                    ),
+                    _ => {}
+                }
+            }
+        }
+
+        let algorithm_errors = self
+            .algorithms
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, params)| {
+                params
+                    .validate()
+                    .err()
+                    .map(|e| format!("algorithms[{}] ({}): {}", idx, params.type_name(), e))
+            })
+            .collect::<Vec<String>>();
+        errors.extend(algorithm_errors);
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors.join("\n"))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn example_config_resolves() {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("experiment.example.toml");
+        let config = ExperimentConfig::load(&path).unwrap().resolve().unwrap();
+        config.validate().unwrap();
+
+        // 4 явные конфигурации + 27 точек AC (одна совпадает с явной) + 8 точек SA
+        assert_eq!(config.algorithms.len(), 4 + 26 + 8);
+        let hashes: HashSet<String> = config.algorithms.iter().map(config_hash).collect();
+        assert_eq!(hashes.len(), config.algorithms.len());
+    }
+}
@@ -1,138 +1,119 @@
 use chrono::Local;
-use regex::Regex;
 use std::{
     collections::HashSet,
-    env::current_dir,
-    fs::{self, DirEntry, File, OpenOptions},
+    fs,
     io::{BufRead, BufReader},
-    time::UNIX_EPOCH,
+    path::{Path, PathBuf},
 };
 
+use crate::append_file::AppendFile;
+use crate::compression::Compression;
+use crate::run_manifest::{RunManifest, RUN_MANIFEST_FILE};
+
+const DATASET_FILE: &str = "dataset.csv";
+const JSONL_FILE: &str = "dataset.jsonl";
+const DATASET_DIR: &str = "dataset";
+const LOG_FILE: &str = "log.csv";
+const JOURNAL_FILE: &str = "journal.csv";
+const ITERATIONS_FILE: &str = "iterations.csv";
+pub const SQLITE_FILE: &str = "results.sqlite";
+
+/// Файлы одного расчёта. Всё лежит в собственном каталоге расчёта:
+/// `run.json`, `journal.csv`, `log.csv` и файлы выбранных приёмников
+/// результатов (`dataset.csv`, `dataset.jsonl`, `dataset/`, `results.sqlite`,
+/// `iterations.csv`). При сжатии к именам датасетов, таблицы итераций и лога
+/// добавляется расширение `.gz` или `.zst`.
 pub struct FileManager {
-    pub log_file: File,
-    pub dataset_file: File,
+    pub run_dir: PathBuf,
+    pub dataset_path: PathBuf,
+    pub jsonl_path: PathBuf,
+    pub parquet_dir: PathBuf,
+    pub sqlite_path: PathBuf,
+    pub log_path: PathBuf,
     pub log_entries: HashSet<String>,
+    pub journal_path: PathBuf,
+    pub iterations_path: PathBuf,
 }
 
 impl FileManager {
-    pub fn new(matrices_count: usize) -> Self {
-        let now = Self::now();
-        let newest_log_file = Self::get_log_file_path();
-
-                if let Some(log_path) = newest_log_file {
-            let old_log_file = File::open(&log_path);
+    /// Имя каталога нового расчёта по умолчанию
+    pub fn run_dir_name() -> String {
+        format!("run_{}", Local::now().format("%Y-%m-%d_%H-%M-%S"))
+    }
 
-            if let Ok(old_log_file) = old_log_file {
-                let old_file_count_finished = BufReader::new(&old_log_file).lines().count();
-                let pattern = Regex::new(r"log_(?<count>\d+)_(?<dt>.*).csv").unwrap();
-                let caps = pattern.captures(&log_path);
-                if let Some(caps) = caps {
-                    let old_file_count_target = caps
-                        .name("count")
-                        .unwrap()
-                        .as_str()
-                        .parse::<usize>()
-                        .unwrap();
-                    if old_file_count_finished < old_file_count_target
-                        && old_file_count_target == matrices_count
-                    {
-                        let log_file = OpenOptions::new().append(true).read(true).open(&log_path);
-                        if let Ok(log_file) = log_file {
-                            let date_time = caps.name("dt").unwrap().as_str().to_string();
-                            let dataset_filename =
-                                Self::get_dataset_filename(matrices_count, date_time);
-                            let dataset = OpenOptions::new().append(true).open(dataset_filename);
-                            if let Ok(dataset_file) = dataset {
-                                let log_reader = BufReader::new(&log_file);
-                                let log_entries = log_reader
-                                    .lines()
-                                    .collect::<Result<HashSet<String>, _>>()
-                                    .unwrap();
+    pub fn exists(run_dir: &Path) -> bool {
+        run_dir.join(RUN_MANIFEST_FILE).is_file()
+    }
 
-                                return Self {
-                                    dataset_file,
-                                    log_file,
-                                    log_entries,
-                                };
-                            }
-                        }
-                    }
-                }
-            }
+    /// Создаёт каталог нового расчёта и записывает в него `run.json`.
+    /// Непустой каталог не используется, чтобы не смешивать результаты разных расчётов.
+    pub fn create(run_dir: &Path, manifest: &RunManifest) -> Result<Self, String> {
+        let is_empty = match fs::read_dir(run_dir) {
+            Ok(mut entries) => entries.next().is_none(),
+            Err(_) => true,
+        };
+        if !is_empty {
+            return Err(format!("Каталог расчёта {:?} не пуст", run_dir));
         }
+        fs::create_dir_all(run_dir)
+            .map_err(|e| format!("Не удалось создать {:?}: {}", run_dir, e))?;
 
-        let dataset_filename = FileManager::get_dataset_filename(matrices_count, now.clone());
-        let log_filename = FileManager::get_log_filename(matrices_count, now.clone());
+        manifest.save(run_dir)?;
 
-        let dataset_file = OpenOptions::new()
-            .write(true)
-            .create(true)
-            .open(dataset_filename)
-            .unwrap();
-        let log_file = OpenOptions::new()
-            .write(true)
-            .create(true)
-            .open(log_filename)
-            .unwrap();
+        Ok(Self::new(run_dir, manifest.compression, HashSet::new()))
+    }
 
+    fn new(run_dir: &Path, compression: Compression, log_entries: HashSet<String>) -> Self {
         Self {
-            dataset_file,
-            log_file,
-            log_entries: HashSet::new(),
+            run_dir: run_dir.to_path_buf(),
+            dataset_path: run_dir.join(compression.file_name(DATASET_FILE)),
+            jsonl_path: run_dir.join(compression.file_name(JSONL_FILE)),
+            parquet_dir: run_dir.join(DATASET_DIR),
+            sqlite_path: run_dir.join(SQLITE_FILE),
+            log_path: run_dir.join(compression.file_name(LOG_FILE)),
+            log_entries,
+            journal_path: run_dir.join(JOURNAL_FILE),
+            iterations_path: run_dir.join(compression.file_name(ITERATIONS_FILE)),
         }
     }
 
-    fn get_dataset_filename(count: usize, dt: String) -> String {
-        format!("dataset_{}_{}.csv", count, dt)
-    }
-
-    fn get_log_filename(count: usize, dt: String) -> String {
-        format!("log_{}_{}.csv", count, dt)
-    }
-
-    fn now() -> String {
-        Local::now().format("%Y-%m-%d_%H_00").to_string()
-    }
-
-    fn get_log_file_path() -> Option<String> {
-        let curr_dir = current_dir().unwrap();
-        let files_list: Vec<DirEntry> = fs::read_dir(curr_dir)
-            .unwrap()
-            .filter_map(|file: Result<DirEntry, _>| {
-                if let Ok(file) = file {
-                    if file.file_name().into_string().unwrap().contains("log_") {
-                        return Some(file);
-                    } else {
-                        return None;
-                    }
-                } else {
-                    return None;
-                }
-            })
-            .collect();
+    /// Открывает существующий расчёт для продолжения, предварительно отбросив
+    /// недописанные строки (фреймы) датасетов и лога
+    pub fn open(run_dir: &Path, compression: Compression) -> Result<Self, String> {
+        if !Self::exists(run_dir) {
+            return Err(format!(
+                "{:?} не является каталогом расчёта: нет {}",
+                run_dir, RUN_MANIFEST_FILE
+            ));
+        }
 
-        if files_list.is_empty() {
-            return None;
-        } else if files_list.len() == 1 {
-            return match files_list.into_iter().nth(0) {
-                Some(entry) => Some(entry.path().to_str().unwrap().to_string()),
-                None => None,
-            };
+        let file_manager = Self::new(run_dir, compression, HashSet::new());
+        for path in [
+            &file_manager.dataset_path,
+            &file_manager.jsonl_path,
+            &file_manager.log_path,
+        ] {
+            let dropped = AppendFile::repair(path)?;
+            if dropped > 0 {
+                println!(
+                    "{:?}: отброшена недописанная строка ({} байт)",
+                    path, dropped
+                );
+            }
         }
 
-        let newest_file = files_list
-            .into_iter()
-            .max_by_key(|entry| {
-                fs::metadata(entry.path())
-                    .and_then(|metadata| metadata.modified())
-                    .unwrap_or(UNIX_EPOCH)
-            })
-            .unwrap()
-            .path()
-            .to_str()
-            .unwrap()
-            .to_string();
+        let log_path = &file_manager.log_path;
+        let log_entries = match Compression::reader(log_path) {
+            Ok(log_file) => BufReader::new(log_file)
+                .lines()
+                .collect::<Result<HashSet<String>, _>>()
+                .map_err(|e| format!("Не удалось прочитать {:?}: {}", log_path, e))?,
+            Err(_) => HashSet::new(),
+        };
 
-        Some(newest_file)
+        Ok(Self {
+            log_entries,
+            ..file_manager
+        })
     }
 }
@@ -0,0 +1,204 @@
+use clap::ValueEnum;
+use ndarray::Array2;
+use ndarray_npy::WriteNpyExt;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use rand_distr::{Distribution, Exp, LogNormal, Normal, Uniform};
+use std::fs::{self, File};
+use std::io::BufWriter;
+use std::path::Path;
+
+use crate::cli::GenerateArgs;
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+pub enum Layout {
+    /// Точки равномерно распределены по квадрату
+    Uniform,
+    /// Точки сгруппированы вокруг случайных центров
+    Clustered,
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+pub enum SpeedProfile {
+    /// Одинаковая скорость на всех рёбрах
+    Constant,
+    /// Скорость падает к центру области (до половины базовой)
+    Urban,
+    /// Длинные переезды быстрее (до удвоенной базовой скорости)
+    Highway,
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+pub enum ImportanceDist {
+    Uniform,
+    Normal,
+    Exponential,
+}
+
+fn instance_seed(seed: u64, size: usize, idx: usize) -> u64 {
+    seed ^ (size as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15)
+        ^ (idx as u64 + 1).wrapping_mul(0xC2B2_AE3D_27D4_EB4F)
+}
+
+fn generate_points(args: &GenerateArgs, size: usize, rng: &mut StdRng) -> Vec<(f64, f64)> {
+    let area = Uniform::new_inclusive(0.0, args.area);
+
+    match args.layout {
+        Layout::Uniform => (0..size)
+            .map(|_| (area.sample(rng), area.sample(rng)))
+            .collect(),
+        Layout::Clustered => {
+            let centers: Vec<(f64, f64)> = (0..args.clusters.max(1))
+                .map(|_| (area.sample(rng), area.sample(rng)))
+                .collect();
+            let spread = Normal::new(0.0, args.cluster_spread * args.area).unwrap();
+
+            (0..size)
+                .map(|_| {
+                    let (cx, cy) = centers[rng.gen_range(0..centers.len())];
+                    (
+                        (cx + spread.sample(rng)).clamp(0.0, args.area),
+                        (cy + spread.sample(rng)).clamp(0.0, args.area),
+                    )
+                })
+                .collect()
+        }
+    }
+}
+
+fn distance_matrix(points: &[(f64, f64)]) -> Array2<f64> {
+    let n = points.len();
+    Array2::from_shape_fn((n, n), |(i, j)| {
+        let (dx, dy) = (points[i].0 - points[j].0, points[i].1 - points[j].1);
+        (dx * dx + dy * dy).sqrt()
+    })
+}
+
+/// Время переезда: расстояние, делённое на скорость профиля, с
+/// мультипликативным логнормальным шумом (матрица получается несимметричной)
+fn time_matrix(
+    args: &GenerateArgs,
+    points: &[(f64, f64)],
+    distance: &Array2<f64>,
+    rng: &mut StdRng,
+) -> Array2<f64> {
+    let n = points.len();
+    let noise = LogNormal::new(0.0, args.time_noise).unwrap();
+    let max_distance = distance
+        .iter()
+        .copied()
+        .fold(0.0, f64::max)
+        .max(f64::EPSILON);
+    let center = args.area / 2.0;
+    let max_radius = (2.0 * center * center).sqrt().max(f64::EPSILON);
+    let radius = |(x, y): (f64, f64)| ((x - center).powi(2) + (y - center).powi(2)).sqrt();
+
+    Array2::from_shape_fn((n, n), |(i, j)| {
+        if i == j {
+            return 0.0;
+        }
+        let d = distance[[i, j]];
+        let speed = match args.speed_profile {
+            SpeedProfile::Constant => args.speed,
+            SpeedProfile::Urban => {
+                let r = (radius(points[i]) + radius(points[j])) / 2.0 / max_radius;
+                args.speed * (0.5 + 0.5 * r)
+            }
+            SpeedProfile::Highway => args.speed * (1.0 + d / max_distance),
+        };
+        d / speed * noise.sample(rng)
+    })
+}
+
+/// Важность посещения вершины: `importance[i][j]` - ценность вершины `j`
+fn importance_matrix(args: &GenerateArgs, size: usize, rng: &mut StdRng) -> Array2<f64> {
+    let weights: Vec<f64> = match args.importance_dist {
+        ImportanceDist::Uniform => {
+            let dist = Uniform::new_inclusive(args.importance_low, args.importance_high);
+            (0..size).map(|_| dist.sample(rng)).collect()
+        }
+        ImportanceDist::Normal => {
+            let dist = Normal::new(args.importance_mean, args.importance_std).unwrap();
+            (0..size).map(|_| dist.sample(rng).max(0.0)).collect()
+        }
+        ImportanceDist::Exponential => {
+            let dist = Exp::new(args.importance_rate).unwrap();
+            (0..size).map(|_| dist.sample(rng)).collect()
+        }
+    };
+
+    Array2::from_shape_fn((size, size), |(i, j)| if i == j { 0.0 } else { weights[j] })
+}
+
+fn write_matrix(dir: &Path, file_name: String, matrix: &Array2<f64>) -> Result<(), String> {
+    let path = dir.join(file_name);
+    let file = File::create(&path).map_err(|e| format!("{:?}: {}", path, e))?;
+    matrix
+        .write_npy(BufWriter::new(file))
+        .map_err(|e| format!("{:?}: {}", path, e))
+}
+
+fn positive(value: f64) -> bool {
+    value.is_finite() && value > 0.0
+}
+
+fn non_negative(value: f64) -> bool {
+    value.is_finite() && value >= 0.0
+}
+
+fn check_args(args: &GenerateArgs) -> Result<(), String> {
+    if args.sizes.iter().any(|size| *size < 2) {
+        return Err("--sizes: every size must be >= 2".to_string());
+    }
+    if !positive(args.area) || !positive(args.speed) {
+        return Err("--area and --speed must be > 0".to_string());
+    }
+    if !non_negative(args.time_noise) || !non_negative(args.cluster_spread) {
+        return Err("--time-noise and --cluster-spread must be >= 0".to_string());
+    }
+    if !non_negative(args.importance_std)
+        || !positive(args.importance_rate)
+        || !args.importance_low.is_finite()
+        || !args.importance_high.is_finite()
+        || args.importance_low > args.importance_high
+    {
+        return Err("invalid importance distribution parameters".to_string());
+    }
+    Ok(())
+}
+
+/// Генерирует тройки `distance_/time_/importance_<id>_<size>.npy`; каждый
+/// экземпляр получает собственное зерно, выведенное из `--seed`, размера и
+/// номера, поэтому отдельные размеры можно перегенерировать независимо.
+pub fn generate(args: &GenerateArgs) -> Result<usize, String> {
+    check_args(args)?;
+    fs::create_dir_all(&args.output_dir).map_err(|e| format!("{:?}: {}", args.output_dir, e))?;
+
+    let mut count = 0;
+    for &size in &args.sizes {
+        for idx in 0..args.instances {
+            let mut rng = StdRng::seed_from_u64(instance_seed(args.seed, size, idx));
+            let id = format!("gen{}-{}-{}", args.seed, size, idx);
+
+            let points = generate_points(args, size, &mut rng);
+            let distance = distance_matrix(&points);
+            let time = time_matrix(args, &points, &distance, &mut rng);
+            let importance = importance_matrix(args, size, &mut rng);
+
+            for (criterion, matrix) in [
+                ("distance", &distance),
+                ("time", &time),
+                ("importance", &importance),
+            ] {
+                write_matrix(
+                    &args.output_dir,
+                    format!("{}_{}_{}.npy", criterion, id, size),
+                    matrix,
+                )?;
+            }
+            count += 1;
+        }
+    }
+
+    Ok(count)
+}
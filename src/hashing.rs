@@ -0,0 +1,50 @@
+use ndarray::Array2;
+
+/// FNV-1a (64 бит): в отличие от `DefaultHasher`, результат не меняется между
+/// версиями Rust, поэтому хеши можно хранить в журнале и датасете
+pub struct ContentHasher(u64);
+
+impl Default for ContentHasher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ContentHasher {
+    pub fn new() -> Self {
+        Self(0xcbf2_9ce4_8422_2325)
+    }
+
+    pub fn update(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 ^= *byte as u64;
+            self.0 = self.0.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+
+    /// Учитывает имя критерия, размерность и значения матрицы, поэтому
+    /// одинаковые данные под разными критериями дают разные хеши
+    pub fn update_matrix(&mut self, name: &str, matrix: &Array2<f64>) {
+        self.update(name.as_bytes());
+        let (rows, cols) = matrix.dim();
+        self.update(&(rows as u64).to_le_bytes());
+        self.update(&(cols as u64).to_le_bytes());
+        for value in matrix.iter() {
+            self.update(&value.to_le_bytes());
+        }
+    }
+
+    pub fn finish(&self) -> u64 {
+        self.0
+    }
+
+    pub fn hex(&self) -> String {
+        format!("{:016x}", self.0)
+    }
+}
+
+pub fn hash_hex(bytes: &[u8]) -> String {
+    let mut hasher = ContentHasher::new();
+    hasher.update(bytes);
+    hasher.hex()
+}
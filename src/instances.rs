@@ -0,0 +1,440 @@
+use phd_cand_algorithms::types::{Purpose, Task};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::loaders::{self, npz};
+use crate::preprocessing::Pipelines;
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CriterionPurpose {
+    Min,
+    Max,
+}
+
+impl CriterionPurpose {
+    pub fn to_purpose(self) -> Purpose {
+        match self {
+            CriterionPurpose::Min => Purpose::Min,
+            CriterionPurpose::Max => Purpose::Max,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Criterion {
+    pub name: String,
+    pub purpose: CriterionPurpose,
+    #[serde(default = "default_required")]
+    pub required: bool,
+}
+
+fn default_required() -> bool {
+    true
+}
+
+pub fn default_criteria() -> Vec<Criterion> {
+    [
+        ("distance", CriterionPurpose::Min),
+        ("time", CriterionPurpose::Min),
+        ("importance", CriterionPurpose::Max),
+    ]
+    .into_iter()
+    .map(|(name, purpose)| Criterion {
+        name: name.to_string(),
+        purpose,
+        required: true,
+    })
+    .collect()
+}
+
+#[derive(Clone, Debug)]
+pub struct CriterionMatrix {
+    pub name: String,
+    pub purpose: CriterionPurpose,
+    pub path: PathBuf,
+}
+
+/// Один экземпляр задачи: матрицы всех найденных критериев с общим ID и размером
+#[derive(Clone, Debug)]
+pub struct Instance {
+    pub id: String,
+    pub size: Option<usize>,
+    pub matrices: Vec<CriterionMatrix>,
+    pub metadata: BTreeMap<String, Value>,
+}
+
+impl Instance {
+    /// Файлы экземпляра; архив .npz с несколькими критериями учитывается один раз
+    pub fn files(&self) -> Vec<&Path> {
+        let mut seen = HashSet::new();
+        self.matrices
+            .iter()
+            .map(|m| m.path.as_path())
+            .filter(|path| seen.insert(*path))
+            .collect()
+    }
+
+    pub fn criteria(&self) -> Vec<String> {
+        self.matrices.iter().map(|m| m.name.clone()).collect()
+    }
+
+    pub fn file_names(&self) -> Vec<&str> {
+        self.files()
+            .iter()
+            .map(|path| path.file_name().unwrap().to_str().unwrap())
+            .collect()
+    }
+}
+
+/// Загруженный и предобработанный экземпляр, общий для всех запусков алгоритмов
+pub struct PreparedInstance<'a> {
+    pub instance: &'a Instance,
+    pub tasks: Vec<Task>,
+    /// Шаги предобработки, фактически применённые к каждому критерию
+    pub preprocessing: Pipelines,
+    /// Хеш матриц после предобработки
+    pub matrices_hash: String,
+    /// Сохранять ли сами матрицы в каждой строке датасета
+    pub embed_matrices: bool,
+}
+
+pub struct IncompleteInstance {
+    pub id: String,
+    pub reason: String,
+}
+
+#[derive(Default)]
+pub struct Discovery {
+    pub instances: Vec<Instance>,
+    pub orphaned: Vec<PathBuf>,
+    pub incomplete: Vec<IncompleteInstance>,
+}
+
+#[derive(Default)]
+pub struct PartialInstance {
+    size: Option<usize>,
+    files: BTreeMap<String, CriterionMatrix>,
+    pub metadata: BTreeMap<String, Value>,
+    errors: Vec<String>,
+}
+
+impl PartialInstance {
+    pub fn add(&mut self, matrix: CriterionMatrix, size: Option<usize>) {
+        match (self.size, size) {
+            (None, _) => self.size = size,
+            (Some(s), Some(other)) if s != other => self.errors.push(format!(
+                "{:?} has size {}, expected {}",
+                matrix.path, other, s
+            )),
+            _ => {}
+        }
+        if let Some(previous) = self.files.insert(matrix.name.clone(), matrix) {
+            self.errors
+                .push(format!("duplicate {} matrix", previous.name));
+        }
+    }
+
+    pub fn error(&mut self, message: String) {
+        self.errors.push(message);
+    }
+}
+
+impl Discovery {
+    /// Ищет в каталоге файлы вида `<criterion>_<instance-id>_<size>.<ext>`
+    /// (или архивы `<instance-id>_<size>.npz` со всеми критериями сразу) и
+    /// собирает их в экземпляры по ID. Набор критериев экземпляра определяется
+    /// найденными файлами, обязательные критерии из `criteria` должны быть у всех.
+    pub fn scan(dir: &Path, criteria: &[Criterion]) -> Result<Self, String> {
+        let pattern = Regex::new(&format!(
+            r"^(?<criterion>[a-z0-9]+)_(?<id>.+)_(?<size>\d+)\.(?:{})$",
+            loaders::single_matrix_extensions().join("|")
+        ))
+        .unwrap();
+        let archive_pattern = Regex::new(r"^(?<id>.+)_(?<size>\d+)\.npz$").unwrap();
+        let entries =
+            fs::read_dir(dir).map_err(|e| format!("Не удалось открыть {:?}: {}", dir, e))?;
+
+        let mut orphaned: Vec<PathBuf> = vec![];
+        let mut partials: BTreeMap<String, PartialInstance> = BTreeMap::new();
+
+        for entry in entries {
+            let path = match entry {
+                Ok(e) => e.path(),
+                Err(_) => continue,
+            };
+            let file_name = match path.file_name().and_then(|name| name.to_str()) {
+                Some(name) => name.to_string(),
+                None => {
+                    orphaned.push(path);
+                    continue;
+                }
+            };
+
+            if let Some(caps) = archive_pattern.captures(&file_name) {
+                let size = caps["size"].parse::<usize>().ok();
+                let partial = partials.entry(caps["id"].to_string()).or_default();
+                let names = match npz::criteria_in(&path) {
+                    Ok(names) => names,
+                    Err(e) => {
+                        partial.error(format!("{}: {}", file_name, e));
+                        continue;
+                    }
+                };
+
+                for criterion in criteria.iter().filter(|c| names.contains(&c.name)) {
+                    partial.add(
+                        CriterionMatrix {
+                            name: criterion.name.clone(),
+                            purpose: criterion.purpose,
+                            path: path.clone(),
+                        },
+                        size,
+                    );
+                }
+                continue;
+            }
+
+            let (caps, criterion) = match pattern.captures(&file_name) {
+                Some(c) => match criteria.iter().find(|cr| cr.name == c["criterion"]) {
+                    Some(criterion) => (c, criterion),
+                    None => {
+                        orphaned.push(path);
+                        continue;
+                    }
+                },
+                None => {
+                    orphaned.push(path);
+                    continue;
+                }
+            };
+
+            partials.entry(caps["id"].to_string()).or_default().add(
+                CriterionMatrix {
+                    name: criterion.name.clone(),
+                    purpose: criterion.purpose,
+                    path,
+                },
+                caps["size"].parse::<usize>().ok(),
+            );
+        }
+
+        let mut discovery = Self::assemble(partials, criteria);
+        discovery.orphaned = orphaned;
+        discovery.orphaned.sort();
+
+        Ok(discovery)
+    }
+
+    /// Проверяет собранные экземпляры на полноту. Матрицы упорядочиваются как
+    /// в `criteria`, критерии вне конфига (из манифеста) идут следом.
+    pub fn assemble(partials: BTreeMap<String, PartialInstance>, criteria: &[Criterion]) -> Self {
+        let mut discovery = Self::default();
+
+        for (id, mut partial) in partials {
+            let missing: Vec<&str> = criteria
+                .iter()
+                .filter(|c| c.required && !partial.files.contains_key(&c.name))
+                .map(|c| c.name.as_str())
+                .collect();
+            if !missing.is_empty() {
+                partial.error(format!("missing {}", missing.join(", ")));
+            }
+
+            if !partial.errors.is_empty() {
+                discovery.incomplete.push(IncompleteInstance {
+                    id,
+                    reason: partial.errors.join("; "),
+                });
+                continue;
+            }
+
+            let mut matrices: Vec<CriterionMatrix> = criteria
+                .iter()
+                .filter_map(|c| partial.files.remove(&c.name))
+                .collect();
+            matrices.extend(partial.files.into_values());
+
+            discovery.instances.push(Instance {
+                id,
+                size: partial.size,
+                matrices,
+                metadata: partial.metadata,
+            });
+        }
+
+        discovery
+            .instances
+            .sort_by(|a, b| a.size.cmp(&b.size).then_with(|| a.id.cmp(&b.id)));
+
+        discovery
+    }
+
+    pub fn report(&self) {
+        for path in &self.orphaned {
+            println!("Файл не относится ни к одному экземпляру: {:?}", path);
+        }
+        for instance in &self.incomplete {
+            println!("Неполный экземпляр {}: {}", instance.id, instance.reason);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ndarray::Array2;
+    use ndarray_npy::NpzWriter;
+    use std::env;
+    use std::fs::File;
+    use std::process;
+
+    fn scan_dir(name: &str, files: &[&str]) -> PathBuf {
+        let dir = env::temp_dir().join(format!("instances-{}-{}", process::id(), name));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        for file in files {
+            fs::write(dir.join(file), "").unwrap();
+        }
+        dir
+    }
+
+    fn names(instance: &Instance) -> Vec<&str> {
+        instance.matrices.iter().map(|m| m.name.as_str()).collect()
+    }
+
+    #[test]
+    fn scan_groups_files_by_id() {
+        let dir = scan_dir(
+            "group",
+            &[
+                "time_b_20.csv",
+                "distance_b_20.npy",
+                "importance_b_20.tsp",
+                "distance_a_10.csv",
+                "time_a_10.csv",
+                "importance_a_10.csv",
+                "distance_run_7_10.npy",
+                "time_run_7_10.npy",
+                "importance_run_7_10.npy",
+            ],
+        );
+        let discovery = Discovery::scan(&dir, &default_criteria()).unwrap();
+
+        assert!(discovery.incomplete.is_empty());
+        assert!(discovery.orphaned.is_empty());
+        let ids: Vec<&str> = discovery.instances.iter().map(|i| i.id.as_str()).collect();
+        assert_eq!(ids, ["a", "run_7", "b"]);
+        assert_eq!(discovery.instances[2].size, Some(20));
+        for instance in &discovery.instances {
+            assert_eq!(names(instance), ["distance", "time", "importance"]);
+            assert_eq!(instance.files().len(), 3);
+        }
+    }
+
+    #[test]
+    fn scan_reports_incomplete_and_orphaned() {
+        let dir = scan_dir(
+            "incomplete",
+            &[
+                "distance_a_10.csv",
+                "time_a_10.csv",
+                "distance_b_10.csv",
+                "time_b_10.csv",
+                "importance_b_12.csv",
+                "weather_c_10.csv",
+                "notes.txt",
+            ],
+        );
+        let discovery = Discovery::scan(&dir, &default_criteria()).unwrap();
+
+        assert!(discovery.instances.is_empty());
+        let incomplete: Vec<(&str, &str)> = discovery
+            .incomplete
+            .iter()
+            .map(|i| (i.id.as_str(), i.reason.as_str()))
+            .collect();
+        assert_eq!(incomplete[0], ("a", "missing importance"));
+        assert_eq!(incomplete[1].0, "b");
+        assert!(incomplete[1].1.contains("has size 12, expected 10"));
+        assert_eq!(
+            discovery.orphaned,
+            [dir.join("notes.txt"), dir.join("weather_c_10.csv")]
+        );
+    }
+
+    #[test]
+    fn scan_skips_missing_optional_criteria() {
+        let dir = scan_dir(
+            "optional",
+            &["distance_a_10.csv", "time_a_10.csv", "time_b_10.csv"],
+        );
+        let mut criteria = default_criteria();
+        criteria[2].required = false;
+        criteria[1].required = false;
+        let discovery = Discovery::scan(&dir, &criteria).unwrap();
+
+        assert_eq!(discovery.instances.len(), 1);
+        assert_eq!(names(&discovery.instances[0]), ["distance", "time"]);
+        assert_eq!(discovery.incomplete[0].id, "b");
+    }
+
+    #[test]
+    fn scan_reads_criteria_from_npz_members() {
+        let dir = scan_dir("npz", &["importance_b_10.csv"]);
+        for (file, members) in [
+            (
+                "a_10.npz",
+                &["importance", "time", "distance", "weather"][..],
+            ),
+            ("b_10.npz", &["distance", "time"][..]),
+        ] {
+            let mut npz = NpzWriter::new(File::create(dir.join(file)).unwrap());
+            for member in members {
+                npz.add_array(*member, &Array2::<f64>::zeros((10, 10)))
+                    .unwrap();
+            }
+            npz.finish().unwrap();
+        }
+        let discovery = Discovery::scan(&dir, &default_criteria()).unwrap();
+
+        assert!(discovery.incomplete.is_empty());
+        let [a, b] = &discovery.instances[..] else {
+            panic!("ожидается два экземпляра");
+        };
+        assert_eq!(names(a), ["distance", "time", "importance"]);
+        assert_eq!(a.file_names(), ["a_10.npz"]);
+        assert_eq!(names(b), ["distance", "time", "importance"]);
+        assert_eq!(b.file_names(), ["b_10.npz", "importance_b_10.csv"]);
+    }
+
+    #[test]
+    fn assemble_keeps_criteria_order_and_extra_matrices() {
+        let matrix = |name: &str| CriterionMatrix {
+            name: name.to_string(),
+            purpose: CriterionPurpose::Min,
+            path: PathBuf::from(format!("{}.csv", name)),
+        };
+        let mut partial = PartialInstance::default();
+        for name in ["cost", "time", "importance", "distance"] {
+            partial.add(matrix(name), Some(5));
+        }
+        let mut duplicated = PartialInstance::default();
+        for name in ["distance", "time", "importance", "time"] {
+            duplicated.add(matrix(name), Some(5));
+        }
+        let partials = BTreeMap::from([("x".to_string(), partial), ("y".to_string(), duplicated)]);
+        let discovery = Discovery::assemble(partials, &default_criteria());
+
+        assert_eq!(
+            names(&discovery.instances[0]),
+            ["distance", "time", "importance", "cost"]
+        );
+        assert_eq!(discovery.incomplete[0].id, "y");
+        assert_eq!(discovery.incomplete[0].reason, "duplicate time matrix");
+    }
+}
@@ -0,0 +1,130 @@
+use csv::{ReaderBuilder, WriterBuilder};
+use std::collections::HashSet;
+use std::fs::File;
+use std::path::Path;
+
+use crate::append_file::AppendFile;
+use crate::classes::algorithm_params::AlgorithmParams;
+use crate::classes::run_algo::RunAlgoResult;
+use crate::hashing::{self, ContentHasher};
+
+/// Один запуск алгоритма: экземпляр, конкретная конфигурация и номер повторения
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct JobKey {
+    pub instance_id: String,
+    pub config_hash: String,
+    pub repetition: usize,
+}
+
+impl JobKey {
+    pub fn new(instance_id: &str, params: &AlgorithmParams, repetition: usize) -> Self {
+        Self {
+            instance_id: instance_id.to_string(),
+            config_hash: config_hash(params),
+            repetition,
+        }
+    }
+
+    pub fn of(row: &RunAlgoResult) -> Self {
+        Self {
+            instance_id: row.instance_id.clone(),
+            config_hash: row.config_hash.clone(),
+            repetition: row.repetition,
+        }
+    }
+
+    /// Зерно запуска выводится из базового зерна эксперимента и ключа, поэтому
+    /// у повторений оно разное, а при продолжении расчёта - то же самое.
+    /// 63 бита, чтобы помещалось в знаковые целые столбцы (SQLite, Parquet).
+    pub fn seed(&self, base_seed: u64) -> u64 {
+        let mut hasher = ContentHasher::new();
+        hasher.update(&base_seed.to_le_bytes());
+        hasher.update(self.instance_id.as_bytes());
+        hasher.update(&[0]);
+        hasher.update(self.config_hash.as_bytes());
+        hasher.update(&(self.repetition as u64).to_le_bytes());
+        hasher.finish() >> 1
+    }
+}
+
+pub fn config_hash(params: &AlgorithmParams) -> String {
+    hashing::hash_hex(serde_json::to_string(params).unwrap().as_bytes())
+}
+
+/// Журнал завершённых запусков. Запись добавляется после того, как строка
+/// результата попала в датасет, поэтому при продолжении расчёта повторяются
+/// только незавершённые запуски.
+pub struct Journal {
+    file: AppendFile,
+    done: HashSet<JobKey>,
+}
+
+impl Journal {
+    pub fn open(path: &Path) -> Result<Self, String> {
+        AppendFile::repair(path)?;
+        let file = AppendFile::open(path)?;
+
+        let mut done: HashSet<JobKey> = HashSet::new();
+        let reader =
+            File::open(path).map_err(|e| format!("Не удалось открыть журнал {:?}: {}", path, e))?;
+        let mut reader = ReaderBuilder::new()
+            .has_headers(false)
+            .flexible(true)
+            .from_reader(reader);
+        for record in reader.records().flatten() {
+            if let (Some(instance_id), Some(config_hash)) = (record.get(0), record.get(1)) {
+                // В журналах без столбца повторения каждая конфигурация запускалась один раз
+                let repetition = match record.get(2) {
+                    Some(repetition) => match repetition.parse() {
+                        Ok(repetition) => repetition,
+                        Err(_) => continue,
+                    },
+                    None => 0,
+                };
+                done.insert(JobKey {
+                    instance_id: instance_id.to_string(),
+                    config_hash: config_hash.to_string(),
+                    repetition,
+                });
+            }
+        }
+
+        Ok(Self { file, done })
+    }
+
+    pub fn done(&self) -> &HashSet<JobKey> {
+        &self.done
+    }
+
+    pub fn record(&mut self, key: JobKey) -> Result<(), String> {
+        let mut writer = WriterBuilder::new().has_headers(false).from_writer(vec![]);
+        writer
+            .write_record([
+                &key.instance_id,
+                &key.config_hash,
+                &key.repetition.to_string(),
+            ])
+            .map_err(|e| e.to_string())?;
+        let line = writer.into_inner().map_err(|e| e.to_string())?;
+
+        self.file.append(&line)?;
+        self.done.insert(key);
+
+        Ok(())
+    }
+
+    /// Результат сохраняется раньше записи журнала, поэтому после аварийного
+    /// завершения может оказаться сохранённым запуск, которого нет в журнале.
+    /// Такие запуски заносятся в журнал, чтобы не посчитать их повторно.
+    pub fn recover(&mut self, stored: Vec<JobKey>) -> Result<usize, String> {
+        let mut recovered = 0;
+        for key in stored {
+            if !self.done.contains(&key) {
+                self.record(key)?;
+                recovered += 1;
+            }
+        }
+
+        Ok(recovered)
+    }
+}
@@ -0,0 +1,38 @@
+use csv::{ReaderBuilder, Trim};
+use ndarray::Array2;
+use std::path::Path;
+
+use super::MatrixLoader;
+
+/// Матрица в CSV без заголовка, по строке матрицы на строку файла
+pub struct CsvLoader;
+
+impl MatrixLoader for CsvLoader {
+    fn extensions(&self) -> &'static [&'static str] {
+        &["csv"]
+    }
+
+    fn load(&self, path: &Path, _criterion: &str) -> Result<Array2<f64>, String> {
+        let mut reader = ReaderBuilder::new()
+            .has_headers(false)
+            .trim(Trim::All)
+            .from_path(path)
+            .map_err(|e| e.to_string())?;
+
+        let mut values: Vec<f64> = vec![];
+        let mut rows = 0;
+        for record in reader.records() {
+            let record = record.map_err(|e| e.to_string())?;
+            for field in record.iter() {
+                let value = field
+                    .parse::<f64>()
+                    .map_err(|e| format!("row {}: {:?}: {}", rows + 1, field, e))?;
+                values.push(value);
+            }
+            rows += 1;
+        }
+
+        let cols = values.len().checked_div(rows).unwrap_or(0);
+        Array2::from_shape_vec((rows, cols), values).map_err(|e| e.to_string())
+    }
+}
@@ -0,0 +1,58 @@
+pub mod csv_matrix;
+pub mod npy;
+pub mod npz;
+pub mod tsplib;
+
+use ndarray::Array2;
+use std::path::Path;
+
+/// Загрузчик матрицы одного критерия, выбирается по расширению файла
+pub trait MatrixLoader: Sync {
+    fn extensions(&self) -> &'static [&'static str];
+
+    /// `criterion` нужен форматам, которые хранят несколько критериев в одном файле
+    fn load(&self, path: &Path, criterion: &str) -> Result<Array2<f64>, String>;
+}
+
+static LOADERS: [&dyn MatrixLoader; 4] = [
+    &npy::NpyLoader,
+    &csv_matrix::CsvLoader,
+    &tsplib::TsplibLoader,
+    &npz::NpzLoader,
+];
+
+fn extension(path: &Path) -> Option<String> {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| ext.to_lowercase())
+}
+
+pub fn loader_for(path: &Path) -> Option<&'static dyn MatrixLoader> {
+    let ext = extension(path)?;
+    LOADERS
+        .iter()
+        .find(|loader| loader.extensions().contains(&ext.as_str()))
+        .copied()
+}
+
+/// Расширения файлов, в которых лежит матрица ровно одного критерия
+pub fn single_matrix_extensions() -> Vec<&'static str> {
+    LOADERS
+        .iter()
+        .flat_map(|loader| loader.extensions())
+        .filter(|ext| !npz::NpzLoader.extensions().contains(*ext))
+        .copied()
+        .collect()
+}
+
+pub fn load_matrix(path: &Path, criterion: &str) -> Result<Array2<f64>, String> {
+    let loader =
+        loader_for(path).ok_or_else(|| format!("{:?}: неизвестный формат матрицы", path))?;
+    loader
+        .load(path, criterion)
+        .map_err(|e| format!("{:?}: {}", path, e))
+}
+
+pub fn to_task_matrix(matrix: &Array2<f64>) -> Vec<Vec<f64>> {
+    matrix.outer_iter().map(|row| row.to_vec()).collect()
+}
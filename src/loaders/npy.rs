@@ -0,0 +1,19 @@
+use ndarray::Array2;
+use ndarray_npy::ReadNpyExt;
+use std::fs::File;
+use std::path::Path;
+
+use super::MatrixLoader;
+
+pub struct NpyLoader;
+
+impl MatrixLoader for NpyLoader {
+    fn extensions(&self) -> &'static [&'static str] {
+        &["npy"]
+    }
+
+    fn load(&self, path: &Path, _criterion: &str) -> Result<Array2<f64>, String> {
+        let file = File::open(path).map_err(|e| e.to_string())?;
+        Array2::<f64>::read_npy(file).map_err(|e| e.to_string())
+    }
+}
@@ -0,0 +1,40 @@
+use ndarray::Array2;
+use ndarray_npy::NpzReader;
+use std::fs::File;
+use std::path::Path;
+
+use super::MatrixLoader;
+
+/// Архив `<instance-id>_<size>.npz`, в котором массивы названы по критериям
+pub struct NpzLoader;
+
+fn open(path: &Path) -> Result<NpzReader<File>, String> {
+    let file = File::open(path).map_err(|e| e.to_string())?;
+    NpzReader::new(file).map_err(|e| e.to_string())
+}
+
+/// Имена критериев, сохранённых в архиве (без суффикса `.npy`)
+pub fn criteria_in(path: &Path) -> Result<Vec<String>, String> {
+    let names = open(path)?.names().map_err(|e| e.to_string())?;
+    Ok(names
+        .into_iter()
+        .map(|name| name.trim_end_matches(".npy").to_string())
+        .collect())
+}
+
+impl MatrixLoader for NpzLoader {
+    fn extensions(&self) -> &'static [&'static str] {
+        &["npz"]
+    }
+
+    fn load(&self, path: &Path, criterion: &str) -> Result<Array2<f64>, String> {
+        let mut npz = open(path)?;
+        let names = npz.names().map_err(|e| e.to_string())?;
+        let name = names
+            .into_iter()
+            .find(|name| name.trim_end_matches(".npy") == criterion)
+            .ok_or_else(|| format!("no {} array in archive", criterion))?;
+
+        npz.by_name(&name).map_err(|e| e.to_string())
+    }
+}
@@ -0,0 +1,214 @@
+use ndarray::Array2;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use super::MatrixLoader;
+
+/// Файлы TSPLIB с `EDGE_WEIGHT_TYPE: EXPLICIT` или `EUC_2D`
+pub struct TsplibLoader;
+
+impl MatrixLoader for TsplibLoader {
+    fn extensions(&self) -> &'static [&'static str] {
+        &["tsp", "atsp"]
+    }
+
+    fn load(&self, path: &Path, _criterion: &str) -> Result<Array2<f64>, String> {
+        let content = fs::read_to_string(path).map_err(|e| e.to_string())?;
+        parse(&content)
+    }
+}
+
+fn parse(content: &str) -> Result<Array2<f64>, String> {
+    let mut header: HashMap<String, String> = HashMap::new();
+    let mut sections: HashMap<String, Vec<&str>> = HashMap::new();
+    let mut current_section: Option<String> = None;
+
+    for line in content.lines() {
+        let line = line.trim();
+        if line.is_empty() || line == "EOF" {
+            continue;
+        }
+
+        if let Some((key, value)) = line.split_once(':') {
+            header.insert(key.trim().to_uppercase(), value.trim().to_string());
+            current_section = None;
+        } else if line.ends_with("_SECTION") {
+            current_section = Some(line.to_string());
+            sections.entry(line.to_string()).or_default();
+        } else if let Some(section) = &current_section {
+            sections
+                .get_mut(section)
+                .unwrap()
+                .extend(line.split_whitespace());
+        }
+    }
+
+    let dimension = header
+        .get("DIMENSION")
+        .ok_or("DIMENSION is missing")?
+        .parse::<usize>()
+        .map_err(|e| format!("DIMENSION: {}", e))?;
+    let edge_weight_type = header
+        .get("EDGE_WEIGHT_TYPE")
+        .ok_or("EDGE_WEIGHT_TYPE is missing")?;
+
+    match edge_weight_type.as_str() {
+        "EXPLICIT" => {
+            let format = header
+                .get("EDGE_WEIGHT_FORMAT")
+                .map(String::as_str)
+                .unwrap_or("FULL_MATRIX");
+            let values = parse_numbers(sections.get("EDGE_WEIGHT_SECTION"))?;
+            explicit_matrix(dimension, format, &values)
+        }
+        "EUC_2D" => {
+            let values = parse_numbers(sections.get("NODE_COORD_SECTION"))?;
+            euclidean_matrix(dimension, &values)
+        }
+        other => Err(format!("unsupported EDGE_WEIGHT_TYPE {}", other)),
+    }
+}
+
+fn parse_numbers(section: Option<&Vec<&str>>) -> Result<Vec<f64>, String> {
+    section
+        .ok_or("data section is missing")?
+        .iter()
+        .map(|token| {
+            token
+                .parse::<f64>()
+                .map_err(|e| format!("{:?}: {}", token, e))
+        })
+        .collect()
+}
+
+fn explicit_matrix(dimension: usize, format: &str, values: &[f64]) -> Result<Array2<f64>, String> {
+    let n = dimension;
+    let cells: Vec<(usize, usize)> = match format {
+        "FULL_MATRIX" => (0..n).flat_map(|i| (0..n).map(move |j| (i, j))).collect(),
+        "UPPER_ROW" => (0..n)
+            .flat_map(|i| (i + 1..n).map(move |j| (i, j)))
+            .collect(),
+        "LOWER_ROW" => (0..n).flat_map(|i| (0..i).map(move |j| (i, j))).collect(),
+        "UPPER_DIAG_ROW" => (0..n).flat_map(|i| (i..n).map(move |j| (i, j))).collect(),
+        "LOWER_DIAG_ROW" => (0..n).flat_map(|i| (0..=i).map(move |j| (i, j))).collect(),
+        other => return Err(format!("unsupported EDGE_WEIGHT_FORMAT {}", other)),
+    };
+
+    if cells.len() != values.len() {
+        return Err(format!(
+            "{} expects {} values for DIMENSION {}, found {}",
+            format,
+            cells.len(),
+            n,
+            values.len()
+        ));
+    }
+
+    let symmetric = format != "FULL_MATRIX";
+    let mut matrix = Array2::<f64>::zeros((n, n));
+    for (&(i, j), &value) in cells.iter().zip(values) {
+        matrix[[i, j]] = value;
+        if symmetric {
+            matrix[[j, i]] = value;
+        }
+    }
+
+    Ok(matrix)
+}
+
+fn euclidean_matrix(dimension: usize, values: &[f64]) -> Result<Array2<f64>, String> {
+    if values.len() != dimension * 3 {
+        return Err(format!(
+            "NODE_COORD_SECTION expects {} nodes, found {} values",
+            dimension,
+            values.len()
+        ));
+    }
+
+    let coords: Vec<(f64, f64)> = values.chunks(3).map(|node| (node[1], node[2])).collect();
+
+    // Расстояния округляются до целого, как требует спецификация TSPLIB (nint)
+    Ok(Array2::from_shape_fn((dimension, dimension), |(i, j)| {
+        let (dx, dy) = (coords[i].0 - coords[j].0, coords[i].1 - coords[j].1);
+        (dx * dx + dy * dy).sqrt().round()
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ndarray::array;
+
+    #[test]
+    fn explicit_full_matrix_keeps_asymmetry() {
+        let content = "\
+NAME: br3
+TYPE: ATSP
+DIMENSION: 3
+EDGE_WEIGHT_TYPE: EXPLICIT
+EDGE_WEIGHT_FORMAT: FULL_MATRIX
+EDGE_WEIGHT_SECTION
+ 0 1 2
+ 3 0 4
+ 5 6 0
+EOF
+";
+        assert_eq!(
+            parse(content).unwrap(),
+            array![[0.0, 1.0, 2.0], [3.0, 0.0, 4.0], [5.0, 6.0, 0.0]]
+        );
+    }
+
+    #[test]
+    fn explicit_upper_row_is_mirrored() {
+        let content = "\
+DIMENSION : 4
+EDGE_WEIGHT_TYPE : EXPLICIT
+EDGE_WEIGHT_FORMAT : UPPER_ROW
+EDGE_WEIGHT_SECTION
+1 2 3
+4 5
+6
+";
+        assert_eq!(
+            parse(content).unwrap(),
+            array![
+                [0.0, 1.0, 2.0, 3.0],
+                [1.0, 0.0, 4.0, 5.0],
+                [2.0, 4.0, 0.0, 6.0],
+                [3.0, 5.0, 6.0, 0.0]
+            ]
+        );
+    }
+
+    #[test]
+    fn euc_2d_rounds_distances() {
+        let content = "\
+DIMENSION: 3
+EDGE_WEIGHT_TYPE: EUC_2D
+NODE_COORD_SECTION
+1 0 0
+2 3 4
+3 1 1
+EOF
+";
+        assert_eq!(
+            parse(content).unwrap(),
+            array![[0.0, 5.0, 1.0], [5.0, 0.0, 4.0], [1.0, 4.0, 0.0]]
+        );
+    }
+
+    #[test]
+    fn value_count_must_match_dimension() {
+        let content = "\
+DIMENSION: 3
+EDGE_WEIGHT_TYPE: EXPLICIT
+EDGE_WEIGHT_FORMAT: UPPER_ROW
+EDGE_WEIGHT_SECTION
+1 2
+";
+        assert!(parse(content).is_err());
+        assert!(parse("DIMENSION: 3\nEDGE_WEIGHT_TYPE: GEO\n").is_err());
+    }
+}
@@ -1,235 +1,479 @@
+mod append_file;
 mod classes;
+mod cli;
+mod compression;
+mod config;
 mod console_log;
 mod file_opener;
+mod generator;
+mod hashing;
+mod instances;
+mod journal;
+mod loaders;
+mod manifest;
+mod preprocessing;
+mod run_manifest;
+mod shutdown;
+mod sinks;
+mod summary;
 mod types;
+mod validation;
 
 use chrono::prelude::*;
 use rayon::prelude::*;
-use regex::Regex;
 
-use csv::Writer;
-use ndarray::Array2;
-use ndarray_npy::ReadNpyExt;
+use append_file::AppendFile;
 use types::{FileRow, SenderInfo};
 
-use std::cmp::Ordering;
-use std::env;
-use std::fs::DirEntry;
+use std::collections::{HashMap, HashSet};
 use std::sync::mpsc::{self, Receiver, Sender};
 use std::sync::Arc;
-use std::{
-    env::current_dir,
-    fs::{self, File},
-    path::Path,
-    thread,
-};
+use std::time::Duration;
+use std::{path::Path, process, thread};
 
-use classes::algorithms::ALGORITHMS;
-use classes::run_algo::run_algo;
+use clap::Parser;
+use classes::run_algo::{self, run_algo, RunStatus};
+use cli::{Cli, Command, ExecutionArgs, ResumeArgs, RunArgs};
+use config::ExperimentConfig;
 
 use console_log::Logger;
-use file_opener::FileManager;
-use phd_cand_algorithms::types::{Purpose, Task};
-use std::io::prelude::*;
-
-const MATRICES_DIR: &'static str = "matrices";
-const MATRICES_COUNT_TARGET_DEFAULT: usize = 100;
+use file_opener::{FileManager, SQLITE_FILE};
+use hashing::ContentHasher;
+use instances::{Criterion, Discovery, Instance, PreparedInstance};
+use journal::{JobKey, Journal};
+use phd_cand_algorithms::types::Task;
+use preprocessing::Pipelines;
+use run_manifest::RunManifest;
+use sinks::{ResultSink, SinkOptions, SqliteStore};
+use validation::{InstanceReport, ValidationPolicy};
 
 fn process_matrix(
     logger: Arc<Logger>,
-    distance_path: &Path,
-    time_path: &Path,
-    importance_path: &Path,
+    experiment: &ExperimentConfig,
+    done_jobs: &HashSet<JobKey>,
+    embed_matrices: bool,
+    instance: &Instance,
     csv_sender: Sender<SenderInfo>,
 ) {
+    if shutdown::requested() {
+        return;
+    }
+
     let mut tasks: Vec<Task> = vec![];
-    let mut file_names: Vec<&str> = vec![];
-
-    for (name, path, purpose) in vec![
-        ("distance", distance_path, Purpose::Min),
-        ("time", time_path, Purpose::Min),
-        ("importance", importance_path, Purpose::Max),
-    ] {
-        let file = match File::open(path) {
-            Ok(f) => f,
-            Err(_) => {
-                println!("Не удалось открыть файл {:?}", path);
+    let mut hasher = ContentHasher::new();
+    let mut applied_preprocessing = Pipelines::new();
+    let file_names = instance.file_names();
+
+    for criterion in &instance.matrices {
+        let matrix = match loaders::load_matrix(&criterion.path, &criterion.name) {
+            Ok(mt) => mt,
+            Err(e) => {
+                println!("Не удалось загрузить матрицу {}", e);
                 return;
             }
         };
 
-        let matrix = match Array2::<f64>::read_npy(file) {
-            Ok(mt) => mt,
-            Err(_) => return,
-        }
-        .round();
+        let steps = preprocessing::pipeline_for(&experiment.preprocessing, &criterion.name);
+        let matrix = preprocessing::apply(matrix, steps);
+        applied_preprocessing.insert(criterion.name.clone(), steps.to_vec());
+        hasher.update_matrix(&criterion.name, &matrix);
 
-        let matrix_vec: Vec<Vec<f64>> = matrix.outer_iter().map(|row| row.to_vec()).collect();
+        let matrix_vec = loaders::to_task_matrix(&matrix);
 
         tasks.push(Task {
-            name: name.to_string(),
+            name: criterion.name.clone(),
             matrix: matrix_vec,
-            purpose,
+            purpose: criterion.purpose.to_purpose(),
         });
-        file_names.push(path.file_name().unwrap().to_str().unwrap())
     }
 
+    let prepared = PreparedInstance {
+        instance,
+        tasks,
+        preprocessing: applied_preprocessing,
+        matrices_hash: hasher.hex(),
+        embed_matrices,
+    };
+
     logger.log_file(&file_names, "START");
 
-    for params in ALGORITHMS {
-        logger.log_calculation(&file_names, &params, "START", None);
+    for (key, params) in experiment.jobs(&instance.id) {
+        if done_jobs.contains(&key) {
+            continue;
+        }
+        // Файл не попадает в лог обработанных, его оставшиеся запуски
+        // выполнятся при продолжении расчёта
+        if shutdown::requested() {
+            return;
+        }
 
-        let dataset_row = match run_algo(params.clone(), tasks.clone()) {
-            Some(s) => s,
-            None => {
-                logger.log_calculation(&file_names, &params, "ERROR", None);
-                continue;
-            }
-        };
+        logger.log_calculation(&file_names, params, "START", None);
 
+        let seed = key.seed(experiment.seed);
+        let dataset_row = run_algo(
+            params.clone(),
+            key.repetition,
+            seed,
+            &experiment.stopping,
+            experiment.timeout.map(Duration::from_secs_f64),
+            &prepared,
+        );
+
+        let status = match dataset_row.status {
+            RunStatus::Ok => "END",
+            status => status.as_str(),
+        };
         logger.log_calculation(
             &file_names,
-            &params,
-            "END",
+            params,
+            status,
             Some(dataset_row.calculation_time),
         );
+        if dataset_row.status == RunStatus::Cancelled {
+            return;
+        }
 
-        let _ = csv_sender.send(SenderInfo::DatasetRow(dataset_row));
+        // Поток записи завершился с ошибкой: результат сохранить некуда,
+        // остальные запуски выполнятся при продолжении расчёта
+        if csv_sender
+            .send(SenderInfo::DatasetRow(Box::new(dataset_row)))
+            .is_err()
+        {
+            return;
+        }
     }
 
     logger.log_file(&file_names, "END");
 
     for file_name in file_names {
-        let _ = csv_sender.send(SenderInfo::FileRow(FileRow(file_name.to_string())));
+        if csv_sender
+            .send(SenderInfo::FileRow(FileRow(file_name.to_string())))
+            .is_err()
+        {
+            return;
+        }
     }
 }
 
-fn writer_handle(receiver: Receiver<SenderInfo>, file_manager: FileManager) {
-    let FileManager {
-        dataset_file,
-        mut log_file,
-        ..
-    } = file_manager;
-    let mut writer = Writer::from_writer(dataset_file);
+/// Каждый результат передаётся всем приёмникам. Запуск заносится в журнал,
+/// когда у приёмников не остаётся несохранённых строк: до этого момента при
+/// продолжении расчёта он будет посчитан заново.
+fn writer_handle(
+    receiver: Receiver<SenderInfo>,
+    mut log: AppendFile,
+    mut journal: Journal,
+    mut sinks: Vec<Box<dyn ResultSink>>,
+) -> Result<usize, String> {
+    let mut unsaved: Vec<JobKey> = vec![];
+    let mut saved = 0;
 
     for result in receiver {
         match result {
             SenderInfo::FileRow(FileRow(file_path)) => {
-                log_file
-                    .write(format!("{}\n", file_path).as_bytes())
-                    .expect(format!("Unable to write {}", file_path).as_str());
+                log.append(format!("{}\n", file_path).as_bytes())?;
+                for sink in sinks.iter_mut() {
+                    sink.write_progress(&file_path)?;
+                }
             }
             SenderInfo::DatasetRow(row) => {
-                if row.iterations.is_empty() {
-                    continue;
+                for sink in sinks.iter_mut() {
+                    sink.write_result(&row)?;
                 }
-                writer
-                    .serialize(row)
-                    .expect("Не удалось записать результат");
+                unsaved.push(JobKey::of(&row));
             }
         }
+
+        if sinks.iter().all(|sink| sink.pending() == 0) {
+            for key in unsaved.drain(..) {
+                journal.record(key)?;
+                saved += 1;
+            }
+        }
+    }
+
+    for sink in sinks.iter_mut() {
+        sink.finish()?;
+    }
+    for key in unsaved {
+        journal.record(key)?;
+        saved += 1;
     }
+
+    Ok(saved)
 }
 
-fn are_sizes_equal(
-    pattern: &Regex,
-    distance_path: &DirEntry,
-    time_path: &DirEntry,
-    importance_path: &DirEntry,
-) -> bool {
-    let (dist_filename, time_filename, impo_filename) = (
-        distance_path.file_name(),
-        time_path.file_name(),
-        importance_path.file_name(),
-    );
+fn exit_with_error(message: String) -> ! {
+    eprintln!("{}", message);
+    process::exit(1);
+}
 
-    let dist_caps = pattern.captures(dist_filename.to_str().unwrap()).unwrap();
-    let time_caps = pattern.captures(time_filename.to_str().unwrap()).unwrap();
-    let impo_caps = pattern.captures(impo_filename.to_str().unwrap()).unwrap();
+fn load_experiment(args: &RunArgs) -> ExperimentConfig {
+    match &args.config {
+        Some(path) => ExperimentConfig::load(path),
+        None => Ok(ExperimentConfig::default()),
+    }
+    .and_then(|config| config.resolve())
+    .and_then(|config| config.validate().map(|_| config))
+    .unwrap_or_else(|e| exit_with_error(e))
+}
 
-    let dist_size = dist_caps.name("size").unwrap().as_str().parse::<usize>();
-    let time_size = time_caps.name("size").unwrap().as_str().parse::<usize>();
-    let impo_size = impo_caps.name("size").unwrap().as_str().parse::<usize>();
+fn discover(input_dir: &Path, manifest: Option<&Path>, criteria: &[Criterion]) -> Discovery {
+    match manifest {
+        Some(path) => manifest::load(path, criteria),
+        None => Discovery::scan(input_dir, criteria),
+    }
+    .unwrap_or_else(|e| exit_with_error(e))
+}
 
-    return dist_size == time_size && time_size == impo_size;
+/// Отбирает для нового расчёта первые `--limit` экземпляров
+fn collect_instances(
+    args: &RunArgs,
+    criteria: &[Criterion],
+    policy: ValidationPolicy,
+) -> Vec<Instance> {
+    let discovery = discover(&args.input_dir, args.manifest.as_deref(), criteria);
+    discovery.report();
+    if policy == ValidationPolicy::Fail && !discovery.incomplete.is_empty() {
+        exit_with_error(format!(
+            "Неполных экземпляров: {}, расчёт остановлен (--on-invalid skip, чтобы пропустить их)",
+            discovery.incomplete.len()
+        ));
+    }
+
+    discovery.instances.into_iter().take(args.limit).collect()
 }
 
-fn main() {
-    let matrices_count: usize = match env::var("MATRICES_COUNT") {
-        Ok(val) => val.parse().unwrap_or(MATRICES_COUNT_TARGET_DEFAULT),
-        Err(_) => MATRICES_COUNT_TARGET_DEFAULT,
-    };
+/// Находит экземпляры, перечисленные в run.json, в том же порядке
+fn run_instances(manifest: &RunManifest, policy: ValidationPolicy) -> Vec<Instance> {
+    let discovery = discover(
+        &manifest.input_dir,
+        manifest.manifest.as_deref(),
+        &manifest.config.criteria,
+    );
+    let mut found: HashMap<String, Instance> = discovery
+        .instances
+        .into_iter()
+        .map(|instance| (instance.id.clone(), instance))
+        .collect();
 
-    let file_manager = FileManager::new(matrices_count);
-    let logger = Arc::new(Logger::new(file_manager.log_entries.len(), matrices_count));
-    let log_entries = file_manager.log_entries.clone();
-
-    let curr_dir = current_dir().unwrap();
-    let matrices_path = curr_dir.join(MATRICES_DIR);
-    let mut matrices_paths: Vec<_> = fs::read_dir(matrices_path)
-        .unwrap()
-        .filter(|dir_entry| {
-            if log_entries.is_empty() {
-                true
-            } else if let Ok(entry) = dir_entry {
-                log_entries.contains(entry.file_name().to_str().unwrap())
-            } else {
-                false
-            }
-        })
-        .take(matrices_count - log_entries.len())
+    let mut instances: Vec<Instance> = vec![];
+    let mut missing: Vec<&str> = vec![];
+    for id in &manifest.instances {
+        match found.remove(id) {
+            Some(instance) => instances.push(instance),
+            None => missing.push(id),
+        }
+    }
+
+    if !missing.is_empty() {
+        let message = format!("Не найдены экземпляры расчёта: {}", missing.join(", "));
+        if policy == ValidationPolicy::Fail {
+            exit_with_error(format!(
+                "{} (--on-invalid skip, чтобы пропустить их)",
+                message
+            ));
+        }
+        println!("{}", message);
+    }
+
+    instances
+}
+
+/// Проверяет все матрицы до начала расчёта, чтобы не обнаружить битый файл через несколько дней вычислений
+fn check_instances(instances: Vec<Instance>, policy: ValidationPolicy) -> Vec<Instance> {
+    let reports: Vec<InstanceReport> = instances
+        .par_iter()
+        .map(validation::validate_instance)
         .collect();
 
-    let pattern = Regex::new(r".+_(?<size>\d+)\.npy").unwrap();
-    matrices_paths.sort_by(|a, b| {
-        let (a_filename, b_filename) = match (a, b) {
-            (Ok(a_file), Ok(b_file)) => (a_file.file_name(), b_file.file_name()),
-            _ => return Ordering::Equal,
-        };
+    let invalid_count = reports.iter().filter(|r| !r.is_valid()).count();
+    if invalid_count == 0 {
+        return instances;
+    }
 
-        let a_caps = pattern.captures(a_filename.to_str().unwrap()).unwrap();
-        let b_caps = pattern.captures(b_filename.to_str().unwrap()).unwrap();
-
-        let a_size = a_caps
-            .name("size")
-            .unwrap()
-            .as_str()
-            .parse::<usize>()
-            .unwrap();
-        let b_size = b_caps
-            .name("size")
-            .unwrap()
-            .as_str()
-            .parse::<usize>()
-            .unwrap();
-
-        a_size.cmp(&b_size)
-    });
+    for report in reports.iter().filter(|r| !r.is_valid()) {
+        report.print();
+    }
+    if policy == ValidationPolicy::Fail {
+        exit_with_error(format!(
+            "Экземпляров с некорректными матрицами: {}, расчёт остановлен (--on-invalid skip, чтобы пропустить их)",
+            invalid_count
+        ));
+    }
+    println!(
+        "Пропущено экземпляров с некорректными матрицами: {}",
+        invalid_count
+    );
+
+    instances
+        .into_iter()
+        .zip(reports)
+        .filter(|(_, report)| report.is_valid())
+        .map(|(instance, _)| instance)
+        .collect()
+}
+
+fn build_thread_pool(threads: Option<usize>) {
+    if let Some(threads) = threads {
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .build_global()
+            .expect("Не удалось создать пул потоков");
+    }
+}
+
+fn run(args: RunArgs) {
+    build_thread_pool(args.threads);
+    let experiment = load_experiment(&args);
+    let run_dir = args
+        .run_dir
+        .clone()
+        .unwrap_or_else(|| args.output_dir.join(FileManager::run_dir_name()));
+
+    if FileManager::exists(&run_dir) {
+        let manifest = RunManifest::load(&run_dir).unwrap_or_else(|e| exit_with_error(e));
+        if serde_json::to_value(&manifest.config).ok() != serde_json::to_value(&experiment).ok() {
+            exit_with_error(format!(
+                "{:?} был создан с другим конфигом, продолжение расчёта невозможно",
+                run_dir
+            ));
+        }
+        let file_manager = FileManager::open(&run_dir, manifest.compression)
+            .unwrap_or_else(|e| exit_with_error(e));
+        let instances = check_instances(run_instances(&manifest, args.on_invalid), args.on_invalid);
+        println!("Продолжение расчёта {:?}", run_dir);
+        return execute(file_manager, manifest, instances, &args.execution);
+    }
+
+    let instances = collect_instances(&args, &experiment.criteria, args.on_invalid);
+    let instances = check_instances(instances, args.on_invalid);
+    let manifest =
+        RunManifest::new(&args, experiment, &instances).unwrap_or_else(|e| exit_with_error(e));
+    let file_manager =
+        FileManager::create(&run_dir, &manifest).unwrap_or_else(|e| exit_with_error(e));
+    println!("Каталог расчёта: {:?}", run_dir);
+
+    execute(file_manager, manifest, instances, &args.execution)
+}
+
+fn resume(args: ResumeArgs) {
+    build_thread_pool(args.threads);
+    let manifest = RunManifest::load(&args.run_dir).unwrap_or_else(|e| exit_with_error(e));
+    let file_manager = FileManager::open(&args.run_dir, manifest.compression)
+        .unwrap_or_else(|e| exit_with_error(e));
+    let instances = check_instances(run_instances(&manifest, args.on_invalid), args.on_invalid);
 
+    execute(file_manager, manifest, instances, &args.execution)
+}
+
+fn execute(
+    file_manager: FileManager,
+    manifest: RunManifest,
+    instances: Vec<Instance>,
+    execution: &ExecutionArgs,
+) {
+    shutdown::install(Duration::from_secs(execution.grace_period))
+        .unwrap_or_else(|e| exit_with_error(e));
+    let rows_per_part = execution.rows_per_part;
+    let experiment = manifest.config;
+    let run_dir = file_manager.run_dir.clone();
+    let mut journal =
+        Journal::open(&file_manager.journal_path).unwrap_or_else(|e| exit_with_error(e));
+    let options = SinkOptions {
+        rows_per_part,
+        iterations_path: manifest.iterations_path,
+        instances: &instances,
+    };
+    let mut result_sinks: Vec<Box<dyn ResultSink>> = manifest
+        .sinks
+        .iter()
+        .map(|kind| sinks::open(*kind, &file_manager, &options))
+        .collect::<Result<_, _>>()
+        .unwrap_or_else(|e| exit_with_error(e));
+    let (recovered, dropped) =
+        sinks::recover(&mut journal, &mut result_sinks).unwrap_or_else(|e| exit_with_error(e));
+    if recovered > 0 {
+        println!(
+            "Восстановлено записей журнала по сохранённым результатам: {}",
+            recovered
+        );
+    }
+    if dropped > 0 {
+        println!("Отброшено результатов незавершённых запусков: {}", dropped);
+    }
+    let done_jobs = journal.done().clone();
+    let files_count = instances
+        .iter()
+        .map(|instance| instance.files().len())
+        .sum();
+    let logger = Arc::new(Logger::new(file_manager.log_entries.len(), files_count));
+
+    let instances: Vec<Instance> = instances
+        .into_iter()
+        .filter(|instance| {
+            experiment
+                .jobs(&instance.id)
+                .any(|(key, _)| !done_jobs.contains(&key))
+        })
+        .collect();
+    let pending_jobs: usize = instances
+        .iter()
+        .map(|instance| {
+            experiment
+                .jobs(&instance.id)
+                .filter(|(key, _)| !done_jobs.contains(key))
+                .count()
+        })
+        .sum();
+
+    let log = AppendFile::open(&file_manager.log_path).unwrap_or_else(|e| exit_with_error(e));
     let (result_sender, result_receiver) = mpsc::channel();
-    let writer_thread = thread::spawn(move || writer_handle(result_receiver, file_manager));
+    let writer_thread = thread::spawn(move || {
+        let result = writer_handle(result_receiver, log, journal, result_sinks);
+        if result.is_err() {
+            shutdown::request();
+        }
+        result
+    });
 
     let calculation_dt_start = Local::now();
 
-    matrices_paths.par_iter().chunks(3).for_each(|chunk| {
-        if let [Ok(distance), Ok(time), Ok(importance)] = chunk[..] {
-            if are_sizes_equal(&pattern, distance, time, importance) {
-                process_matrix(
-                    logger.clone(),
-                    distance.path().as_path(),
-                    time.path().as_path(),
-                    importance.path().as_path(),
-                    result_sender.clone(),
-                )
-            }
-        }
+    instances.par_iter().for_each(|instance| {
+        process_matrix(
+            logger.clone(),
+            &experiment,
+            &done_jobs,
+            manifest.embed_matrices,
+            instance,
+            result_sender.clone(),
+        )
     });
 
     drop(result_sender);
 
-    writer_thread
+    let saved_jobs = writer_thread
         .join()
-        .expect("writer handle завершился с ошибкой");
+        .expect("writer handle завершился с ошибкой")
+        .unwrap_or_else(|e| exit_with_error(e));
+
+    let detached = run_algo::detached();
+    if detached > 0 {
+        println!(
+            "Потоков алгоритмов, прерванных по тайм-ауту и ещё не завершившихся: {}. \
+             Они продолжают занимать процессор до выхода из программы",
+            detached
+        );
+    }
+
+    if shutdown::requested() {
+        println!(
+            "Расчёт остановлен досрочно: сохранено запусков {}, осталось {}",
+            saved_jobs,
+            pending_jobs.saturating_sub(saved_jobs)
+        );
+        println!("Продолжить: cargo run -- resume {}", run_dir.display());
+        return;
+    }
 
     let calculation_dt_end = Local::now();
     let duration = calculation_dt_end.signed_duration_since(calculation_dt_start);
@@ -239,5 +483,166 @@ fn main() {
         duration.num_hours() % 24,
         duration.num_minutes() % 60,
         duration.num_seconds() % 60
-    )
+    );
+    println!("Результаты: {:?}", run_dir);
+}
+
+/// Без `--run-dir` показывает новый расчёт, с существующим `--run-dir` -
+/// оставшиеся запуски этого расчёта
+fn plan(args: RunArgs) {
+    let existing_run = args
+        .run_dir
+        .as_deref()
+        .filter(|dir| FileManager::exists(dir));
+
+    let (experiment, instances, done_jobs) = match existing_run {
+        Some(run_dir) => {
+            let manifest = RunManifest::load(run_dir).unwrap_or_else(|e| exit_with_error(e));
+            let file_manager = FileManager::open(run_dir, manifest.compression)
+                .unwrap_or_else(|e| exit_with_error(e));
+            let journal =
+                Journal::open(&file_manager.journal_path).unwrap_or_else(|e| exit_with_error(e));
+            println!(
+                "Продолжение расчёта {:?} от {}: уже обработано файлов {}, запусков {}",
+                run_dir,
+                manifest.started_at,
+                file_manager.log_entries.len(),
+                journal.done().len()
+            );
+            let instances = run_instances(&manifest, ValidationPolicy::Skip);
+            (manifest.config, instances, journal.done().clone())
+        }
+        None => {
+            println!("Новый расчёт");
+            let experiment = load_experiment(&args);
+            let instances = collect_instances(&args, &experiment.criteria, ValidationPolicy::Skip);
+            (experiment, instances, HashSet::new())
+        }
+    };
+
+    let pending = |instance: &Instance| -> usize {
+        experiment
+            .jobs(&instance.id)
+            .filter(|(key, _)| !done_jobs.contains(key))
+            .count()
+    };
+
+    let mut instances_count = 0;
+    let mut jobs_count = 0;
+    for instance in &instances {
+        let instance_jobs = pending(instance);
+        if instance_jobs == 0 {
+            continue;
+        }
+        instances_count += 1;
+        jobs_count += instance_jobs;
+        println!(
+            "{:>6} {:<30} {:>4} {}",
+            instance
+                .size
+                .map_or_else(|| "?".to_string(), |size| size.to_string()),
+            instance.id,
+            instance_jobs,
+            instance.file_names().join(" ")
+        );
+    }
+
+    println!(
+        "\nКонфигурации алгоритмов ({}), повторений каждой: {}, базовое зерно: {}",
+        experiment.algorithms.len(),
+        experiment.repetitions,
+        experiment.seed
+    );
+    println!(
+        "Критерий остановки: {}, тайм-аут запуска: {}",
+        serde_json::to_string(&experiment.stopping).unwrap(),
+        experiment
+            .timeout
+            .map_or_else(|| "нет".to_string(), |timeout| format!("{} с", timeout))
+    );
+    for params in &experiment.algorithms {
+        println!(
+            "{} {}",
+            journal::config_hash(params),
+            serde_json::to_string(params).unwrap()
+        );
+    }
+
+    println!(
+        "\nИтого: {} экземпляров, {} запусков",
+        instances_count, jobs_count
+    );
+}
+
+fn validate(args: RunArgs) {
+    let experiment = load_experiment(&args);
+    println!(
+        "Конфиг корректен: {} конфигураций алгоритмов",
+        experiment.algorithms.len()
+    );
+
+    let discovery = discover(
+        &args.input_dir,
+        args.manifest.as_deref(),
+        &experiment.criteria,
+    );
+    discovery.report();
+
+    let reports: Vec<InstanceReport> = discovery
+        .instances
+        .par_iter()
+        .map(validation::validate_instance)
+        .collect();
+    for report in &reports {
+        report.print();
+    }
+
+    let invalid_count = reports.iter().filter(|r| !r.is_valid()).count();
+    println!(
+        "Экземпляров: {}, некорректных: {}, неполных: {}, лишних файлов: {}",
+        reports.len(),
+        invalid_count,
+        discovery.incomplete.len(),
+        discovery.orphaned.len()
+    );
+
+    if invalid_count > 0 || !discovery.incomplete.is_empty() || !discovery.orphaned.is_empty() {
+        process::exit(1);
+    }
+}
+
+fn main() {
+    let cli = Cli::parse();
+
+    match cli.command.unwrap_or(Command::Run(cli.run)) {
+        Command::Run(args) => run(args),
+        Command::Resume(args) => resume(args),
+        Command::Plan(args) => plan(args),
+        Command::Summarize(args) => {
+            summary::summarize(&args.dataset).unwrap_or_else(|e| exit_with_error(e))
+        }
+        Command::Validate(args) => validate(args),
+        Command::Query(args) => {
+            let path = if args.database.is_dir() {
+                args.database.join(SQLITE_FILE)
+            } else {
+                args.database
+            };
+            if !path.is_file() {
+                exit_with_error(format!("{:?} не найден", path));
+            }
+            SqliteStore::open_read_only(&path)
+                .and_then(|store| {
+                    store.print_query(args.sql.as_deref().unwrap_or(sinks::DEFAULT_QUERY))
+                })
+                .unwrap_or_else(|e| exit_with_error(e))
+        }
+        Command::Generate(args) => {
+            let count = generator::generate(&args).unwrap_or_else(|e| exit_with_error(e));
+            println!(
+                "Сгенерировано экземпляров: {} в {:?}",
+                count, args.output_dir
+            );
+        }
+    }
 }
@@ -0,0 +1,149 @@
+use csv::Reader;
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::instances::{Criterion, CriterionMatrix, CriterionPurpose, Discovery, PartialInstance};
+
+#[derive(Deserialize)]
+struct ManifestFile {
+    instances: Vec<ManifestInstance>,
+}
+
+#[derive(Deserialize)]
+struct ManifestInstance {
+    id: String,
+    size: Option<usize>,
+    criteria: Vec<ManifestMatrix>,
+    #[serde(default)]
+    metadata: BTreeMap<String, Value>,
+}
+
+#[derive(Deserialize)]
+struct ManifestMatrix {
+    name: String,
+    path: PathBuf,
+    purpose: Option<CriterionPurpose>,
+}
+
+const CSV_COLUMNS: [&str; 5] = ["instance_id", "criterion", "path", "purpose", "size"];
+
+/// Загружает список экземпляров из manifest.json или manifest.csv.
+///
+/// В CSV каждая строка описывает одну матрицу: `instance_id,criterion,path`
+/// и необязательные `purpose,size`; остальные столбцы считаются метаданными
+/// экземпляра. Относительные пути отсчитываются от каталога манифеста.
+pub fn load(path: &Path, criteria: &[Criterion]) -> Result<Discovery, String> {
+    let base_dir = path.parent().unwrap_or(Path::new("."));
+
+    let instances = match path.extension().and_then(|ext| ext.to_str()) {
+        Some("json") => {
+            let content = fs::read_to_string(path)
+                .map_err(|e| format!("Не удалось прочитать {:?}: {}", path, e))?;
+            serde_json::from_str::<ManifestFile>(&content)
+                .map_err(|e| format!("Некорректный манифест {:?}: {}", path, e))?
+                .instances
+        }
+        Some("csv") => read_csv(path)?,
+        _ => return Err(format!("{:?}: expected a .json or .csv manifest", path)),
+    };
+
+    let mut partials: BTreeMap<String, PartialInstance> = BTreeMap::new();
+
+    for instance in instances {
+        let partial = partials.entry(instance.id).or_default();
+        partial.metadata.extend(instance.metadata);
+
+        for matrix in instance.criteria {
+            let purpose = match matrix.purpose.or_else(|| {
+                criteria
+                    .iter()
+                    .find(|c| c.name == matrix.name)
+                    .map(|c| c.purpose)
+            }) {
+                Some(p) => p,
+                None => {
+                    partial.error(format!("no purpose for criterion {}", matrix.name));
+                    continue;
+                }
+            };
+
+            let matrix_path = base_dir.join(&matrix.path);
+            if !matrix_path.is_file() {
+                partial.error(format!("{:?} not found", matrix_path));
+                continue;
+            }
+
+            partial.add(
+                CriterionMatrix {
+                    name: matrix.name,
+                    purpose,
+                    path: matrix_path,
+                },
+                instance.size,
+            );
+        }
+    }
+
+    Ok(Discovery::assemble(partials, criteria))
+}
+
+fn read_csv(path: &Path) -> Result<Vec<ManifestInstance>, String> {
+    let mut reader =
+        Reader::from_path(path).map_err(|e| format!("Не удалось открыть {:?}: {}", path, e))?;
+    let headers = reader.headers().map_err(|e| e.to_string())?.clone();
+
+    for column in &CSV_COLUMNS[..3] {
+        if !headers.iter().any(|h| h == *column) {
+            return Err(format!("{:?}: missing column {}", path, column));
+        }
+    }
+
+    let mut instances: Vec<ManifestInstance> = vec![];
+
+    for (idx, record) in reader.records().enumerate() {
+        let record = record.map_err(|e| format!("{:?}: {}", path, e))?;
+        let line = idx + 2;
+        let mut row: BTreeMap<&str, &str> = headers.iter().zip(record.iter()).collect();
+
+        let id = row.remove("instance_id").unwrap_or_default().to_string();
+        let name = row.remove("criterion").unwrap_or_default().to_string();
+        let matrix_path = PathBuf::from(row.remove("path").unwrap_or_default());
+
+        let purpose = match row.remove("purpose").unwrap_or_default() {
+            "" => None,
+            "min" => Some(CriterionPurpose::Min),
+            "max" => Some(CriterionPurpose::Max),
+            other => return Err(format!("{:?}:{}: unknown purpose {:?}", path, line, other)),
+        };
+        let size = match row.remove("size").unwrap_or_default() {
+            "" => None,
+            value => Some(
+                value
+                    .parse::<usize>()
+                    .map_err(|e| format!("{:?}:{}: size: {}", path, line, e))?,
+            ),
+        };
+
+        let metadata = row
+            .into_iter()
+            .filter(|(_, value)| !value.is_empty())
+            .map(|(key, value)| (key.to_string(), Value::String(value.to_string())))
+            .collect();
+
+        instances.push(ManifestInstance {
+            id,
+            size,
+            criteria: vec![ManifestMatrix {
+                name,
+                path: matrix_path,
+                purpose,
+            }],
+            metadata,
+        });
+    }
+
+    Ok(instances)
+}
@@ -0,0 +1,125 @@
+use ndarray::Array2;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Ключ конвейера, который применяется к критериям без собственной настройки
+pub const DEFAULT_KEY: &str = "default";
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SymmetrizeMode {
+    #[default]
+    Mean,
+    Min,
+    Max,
+}
+
+/// Шаг предобработки матрицы. Статистики для `min_max` и `z_score`
+/// считаются по недиагональным элементам квадратной матрицы, а диагональ
+/// после них остаётся нулевой.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum PreprocessStep {
+    Round,
+    Decimals {
+        digits: i32,
+    },
+    MinMax,
+    ZScore,
+    Symmetrize {
+        #[serde(default)]
+        mode: SymmetrizeMode,
+    },
+    Scale {
+        factor: f64,
+    },
+    ZeroDiagonal,
+}
+
+pub type Pipelines = BTreeMap<String, Vec<PreprocessStep>>;
+
+/// До появления настройки все матрицы округлялись, это поведение по умолчанию
+pub fn default_pipelines() -> Pipelines {
+    BTreeMap::from([(DEFAULT_KEY.to_string(), vec![PreprocessStep::Round])])
+}
+
+/// Конвейер критерия, иначе `default`. Если в заданной секции `[preprocessing]`
+/// нет ни того, ни другого, матрица используется как есть.
+pub fn pipeline_for<'a>(pipelines: &'a Pipelines, criterion: &str) -> &'a [PreprocessStep] {
+    pipelines
+        .get(criterion)
+        .or_else(|| pipelines.get(DEFAULT_KEY))
+        .map_or(&[], Vec::as_slice)
+}
+
+pub fn apply(mut matrix: Array2<f64>, steps: &[PreprocessStep]) -> Array2<f64> {
+    for step in steps {
+        matrix = apply_step(matrix, step);
+    }
+    matrix
+}
+
+fn off_diagonal(matrix: &Array2<f64>) -> Vec<f64> {
+    matrix
+        .indexed_iter()
+        .filter(|((i, j), _)| i != j || !matrix.is_square())
+        .map(|(_, value)| *value)
+        .collect()
+}
+
+/// Нулевая диагональ квадратной матрицы (путь из вершины в неё же)
+fn zero_diagonal(matrix: &mut Array2<f64>) {
+    if matrix.is_square() {
+        matrix.diag_mut().fill(0.0);
+    }
+}
+
+fn apply_step(mut matrix: Array2<f64>, step: &PreprocessStep) -> Array2<f64> {
+    match step {
+        PreprocessStep::Round => matrix.mapv_inplace(f64::round),
+        PreprocessStep::Decimals { digits } => {
+            let factor = 10f64.powi(*digits);
+            matrix.mapv_inplace(|v| (v * factor).round() / factor);
+        }
+        PreprocessStep::MinMax => {
+            let values = off_diagonal(&matrix);
+            let min = values.iter().copied().fold(f64::INFINITY, f64::min);
+            let max = values.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+            let range = max - min;
+            if range > 0.0 && range.is_finite() {
+                matrix.mapv_inplace(|v| (v - min) / range);
+            } else {
+                matrix.fill(0.0);
+            }
+            zero_diagonal(&mut matrix);
+        }
+        PreprocessStep::ZScore => {
+            let values = off_diagonal(&matrix);
+            let count = values.len().max(1) as f64;
+            let mean = values.iter().sum::<f64>() / count;
+            let std = (values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / count).sqrt();
+            if std > 0.0 && std.is_finite() {
+                matrix.mapv_inplace(|v| (v - mean) / std);
+            } else {
+                matrix.fill(0.0);
+            }
+            zero_diagonal(&mut matrix);
+        }
+        PreprocessStep::Symmetrize { mode } => {
+            if matrix.is_square() {
+                let transposed = matrix.t().to_owned();
+                matrix.zip_mut_with(&transposed, |a, b| {
+                    *a = match mode {
+                        SymmetrizeMode::Mean => (*a + b) / 2.0,
+                        SymmetrizeMode::Min => a.min(*b),
+                        SymmetrizeMode::Max => a.max(*b),
+                    }
+                });
+            }
+        }
+        PreprocessStep::Scale { factor } => matrix.mapv_inplace(|v| v * factor),
+        PreprocessStep::ZeroDiagonal => matrix.diag_mut().fill(0.0),
+    }
+
+    matrix
+}
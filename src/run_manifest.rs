@@ -0,0 +1,91 @@
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::cli::RunArgs;
+use crate::compression::Compression;
+use crate::config::ExperimentConfig;
+use crate::instances::Instance;
+use crate::sinks::{default_sinks, SinkKind};
+
+pub const RUN_MANIFEST_FILE: &str = "run.json";
+
+/// Описание расчёта, по которому его можно продолжить или воспроизвести
+#[derive(Serialize, Deserialize)]
+pub struct RunManifest {
+    pub started_at: String,
+    pub version: String,
+    pub host: String,
+    pub input_dir: PathBuf,
+    pub manifest: Option<PathBuf>,
+    #[serde(default = "default_sinks")]
+    pub sinks: Vec<SinkKind>,
+    #[serde(default)]
+    pub iterations_path: bool,
+    #[serde(default)]
+    pub compression: Compression,
+    /// Матрицы сохраняются в каждой строке датасета
+    #[serde(default)]
+    pub embed_matrices: bool,
+    /// Конфиг с развёрнутыми `sweeps`
+    pub config: ExperimentConfig,
+    /// ID экземпляров, отобранных для расчёта
+    pub instances: Vec<String>,
+}
+
+impl RunManifest {
+    /// Пути к входным данным сохраняются абсолютными: расчёт можно продолжить
+    /// из другого рабочего каталога
+    pub fn new(
+        args: &RunArgs,
+        config: ExperimentConfig,
+        instances: &[Instance],
+    ) -> Result<Self, String> {
+        Ok(Self {
+            started_at: Local::now().to_rfc3339(),
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            host: host_name(),
+            input_dir: absolute(&args.input_dir)?,
+            manifest: args.manifest.as_deref().map(absolute).transpose()?,
+            sinks: args.sinks.clone(),
+            iterations_path: args.iterations_path,
+            compression: args.compression,
+            embed_matrices: args.embed_matrices,
+            config,
+            instances: instances
+                .iter()
+                .map(|instance| instance.id.clone())
+                .collect(),
+        })
+    }
+
+    pub fn load(run_dir: &Path) -> Result<Self, String> {
+        let path = run_dir.join(RUN_MANIFEST_FILE);
+        let content = fs::read_to_string(&path)
+            .map_err(|e| format!("Не удалось прочитать {:?}: {}", path, e))?;
+
+        serde_json::from_str(&content).map_err(|e| format!("Некорректный {:?}: {}", path, e))
+    }
+
+    pub fn save(&self, run_dir: &Path) -> Result<(), String> {
+        let path = run_dir.join(RUN_MANIFEST_FILE);
+        let content = serde_json::to_string_pretty(self).map_err(|e| e.to_string())?;
+
+        fs::write(&path, content).map_err(|e| format!("Не удалось записать {:?}: {}", path, e))
+    }
+}
+
+fn absolute(path: &Path) -> Result<PathBuf, String> {
+    fs::canonicalize(path).map_err(|e| format!("{:?}: {}", path, e))
+}
+
+fn host_name() -> String {
+    env::var("HOSTNAME")
+        .ok()
+        .or_else(|| fs::read_to_string("/etc/hostname").ok())
+        .map(|name| name.trim().to_string())
+        .filter(|name| !name.is_empty())
+        .unwrap_or_else(|| "unknown".to_string())
+}
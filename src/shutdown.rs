@@ -0,0 +1,49 @@
+use std::process;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::Duration;
+
+static REQUESTED: AtomicBool = AtomicBool::new(false);
+/// Сигнал уже был получен; отдельно от `REQUESTED`, который ставит и `request()`
+static SIGNALLED: AtomicBool = AtomicBool::new(false);
+static CANCELLED: AtomicBool = AtomicBool::new(false);
+
+/// Обработка SIGINT/SIGTERM. Первый сигнал запрещает начинать новые запуски
+/// и даёт текущим `grace_period` на завершение, после чего они прерываются
+/// через callback. Второй сигнал завершает процесс сразу: датасеты и журнал
+/// дописываются целыми записями, поэтому расчёт всё равно можно продолжить.
+pub fn install(grace_period: Duration) -> Result<(), String> {
+    ctrlc::set_handler(move || {
+        if SIGNALLED.swap(true, Ordering::SeqCst) {
+            eprintln!("Повторный сигнал остановки, выход без ожидания");
+            process::exit(130);
+        }
+        REQUESTED.store(true, Ordering::SeqCst);
+
+        eprintln!(
+            "Получен сигнал остановки: новые запуски не начинаются, текущие будут прерваны через {} с",
+            grace_period.as_secs()
+        );
+        thread::spawn(move || {
+            thread::sleep(grace_period);
+            CANCELLED.store(true, Ordering::SeqCst);
+        });
+    })
+    .map_err(|e| format!("Не удалось установить обработчик сигналов: {}", e))
+}
+
+/// Остановка без сигнала, например после ошибки записи результатов: новые
+/// запуски не начинаются, текущие доводятся до конца
+pub fn request() {
+    REQUESTED.store(true, Ordering::SeqCst);
+}
+
+/// Получен сигнал остановки: новые запуски не начинаются
+pub fn requested() -> bool {
+    REQUESTED.load(Ordering::SeqCst)
+}
+
+/// Время на завершение истекло: текущие запуски прерываются
+pub fn cancelled() -> bool {
+    CANCELLED.load(Ordering::SeqCst)
+}
@@ -0,0 +1,48 @@
+use csv::WriterBuilder;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use super::ResultSink;
+use crate::append_file::AppendFile;
+use crate::classes::run_algo::RunAlgoResult;
+use crate::journal::JobKey;
+
+/// dataset.csv: строка на каждый запуск, каждая строка пишется целиком
+pub struct CsvSink {
+    file: AppendFile,
+    path: PathBuf,
+}
+
+impl CsvSink {
+    pub fn open(path: &Path) -> Result<Self, String> {
+        Ok(Self {
+            file: AppendFile::open(path)?,
+            path: path.to_path_buf(),
+        })
+    }
+}
+
+impl ResultSink for CsvSink {
+    fn stored(&self) -> Result<Option<Vec<JobKey>>, String> {
+        super::csv_keys(&self.path).map(Some)
+    }
+
+    fn rollback(&mut self, done: &HashSet<JobKey>) -> Result<usize, String> {
+        super::truncate_csv_tail(&self.path, done)
+    }
+
+    fn write_result(&mut self, row: &RunAlgoResult) -> Result<(), String> {
+        if row.is_empty() {
+            return Ok(());
+        }
+
+        // Заголовок пишется вместе с первой строкой, поэтому файл мог стать
+        // пустым после отката незавершённых запусков
+        let mut writer = WriterBuilder::new()
+            .has_headers(self.file.is_empty()?)
+            .from_writer(vec![]);
+        writer.serialize(row).map_err(|e| e.to_string())?;
+        let record = writer.into_inner().map_err(|e| e.to_string())?;
+        self.file.append(&record)
+    }
+}
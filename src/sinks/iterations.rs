@@ -0,0 +1,136 @@
+use csv::{ReaderBuilder, WriterBuilder};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use super::ResultSink;
+use crate::append_file::AppendFile;
+use crate::classes::run_algo::RunAlgoResult;
+use crate::compression::Compression;
+use crate::instances::Instance;
+use crate::journal::JobKey;
+
+const KEY_COLUMNS: [&str; 8] = [
+    "instance_id",
+    "config_hash",
+    "repetition",
+    "algo_type",
+    "iter_num",
+    "calc_time",
+    "evaluations",
+    "weight",
+];
+const PATH_COLUMN: &str = "path";
+
+/// Таблица сходимости в длинном формате: строка на каждую итерацию запуска,
+/// значение каждого критерия в отдельном столбце
+pub struct IterationsTable {
+    file: AppendFile,
+    path: PathBuf,
+    criteria: Vec<String>,
+    with_path: bool,
+}
+
+impl IterationsTable {
+    /// Столбцы критериев - все критерии экземпляров расчёта; для
+    /// существующего файла набор столбцов берётся из его заголовка
+    pub fn open(path: &Path, instances: &[Instance], with_path: bool) -> Result<Self, String> {
+        AppendFile::repair(path)?;
+        let mut file = AppendFile::open(path)?;
+
+        if !file.is_empty()? {
+            let headers = Compression::reader(path)
+                .map(|reader| ReaderBuilder::new().from_reader(reader))
+                .map_err(csv::Error::from)
+                .and_then(|mut reader| reader.headers().cloned())
+                .map_err(|e| format!("{:?}: {}", path, e))?;
+            let columns: Vec<&str> = headers.iter().skip(KEY_COLUMNS.len()).collect();
+            let with_path = columns.last() == Some(&PATH_COLUMN);
+            let criteria_count = columns.len() - usize::from(with_path);
+
+            return Ok(Self {
+                file,
+                path: path.to_path_buf(),
+                criteria: columns[..criteria_count]
+                    .iter()
+                    .map(|name| name.to_string())
+                    .collect(),
+                with_path,
+            });
+        }
+
+        let mut criteria: Vec<String> = vec![];
+        for criterion in instances.iter().flat_map(Instance::criteria) {
+            if !criteria.contains(&criterion) {
+                criteria.push(criterion);
+            }
+        }
+
+        let mut header: Vec<&str> = KEY_COLUMNS.to_vec();
+        header.extend(criteria.iter().map(String::as_str));
+        if with_path {
+            header.push(PATH_COLUMN);
+        }
+        file.append(&to_csv(&[header.iter().map(|c| c.to_string()).collect()])?)?;
+
+        Ok(Self {
+            file,
+            path: path.to_path_buf(),
+            criteria,
+            with_path,
+        })
+    }
+}
+
+impl ResultSink for IterationsTable {
+    fn stored(&self) -> Result<Option<Vec<JobKey>>, String> {
+        super::csv_keys(&self.path).map(Some)
+    }
+
+    fn rollback(&mut self, done: &HashSet<JobKey>) -> Result<usize, String> {
+        super::truncate_csv_tail(&self.path, done)
+    }
+
+    /// Все итерации запуска пишутся одной записью
+    fn write_result(&mut self, row: &RunAlgoResult) -> Result<(), String> {
+        if row.iterations.is_empty() {
+            return Ok(());
+        }
+
+        let records: Vec<Vec<String>> = row
+            .iterations
+            .iter()
+            .map(|iteration| {
+                let mut record = vec![
+                    row.instance_id.clone(),
+                    row.config_hash.clone(),
+                    row.repetition.to_string(),
+                    row.algo.type_name().to_string(),
+                    iteration.iter_num.to_string(),
+                    iteration.calc_time.to_string(),
+                    iteration.evaluations.to_string(),
+                    iteration.weight.to_string(),
+                ];
+                record.extend(self.criteria.iter().map(|criterion| {
+                    iteration
+                        .results
+                        .get(criterion)
+                        .map_or_else(String::new, |value| value.to_string())
+                }));
+                if self.with_path {
+                    record.push(serde_json::to_string(&iteration.path).unwrap());
+                }
+                record
+            })
+            .collect();
+
+        self.file.append(&to_csv(&records)?)
+    }
+}
+
+fn to_csv(records: &[Vec<String>]) -> Result<Vec<u8>, String> {
+    let mut writer = WriterBuilder::new().has_headers(false).from_writer(vec![]);
+    for record in records {
+        writer.write_record(record).map_err(|e| e.to_string())?;
+    }
+    writer.into_inner().map_err(|e| e.to_string())
+}
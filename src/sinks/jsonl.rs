@@ -0,0 +1,110 @@
+use serde::Deserialize;
+use serde_json::json;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use super::ResultSink;
+use crate::append_file::{AppendFile, Records};
+use crate::classes::run_algo::RunAlgoResult;
+use crate::journal::JobKey;
+
+/// dataset.jsonl: объект на каждый запуск, вложенные поля - обычный JSON,
+/// а не строки с JSON внутри, как в CSV
+pub struct JsonlSink {
+    file: AppendFile,
+    path: PathBuf,
+}
+
+#[derive(Deserialize)]
+struct StoredRow {
+    instance_id: String,
+    config_hash: String,
+    #[serde(default)]
+    repetition: usize,
+}
+
+impl JsonlSink {
+    pub fn open(path: &Path) -> Result<Self, String> {
+        Ok(Self {
+            file: AppendFile::open(path)?,
+            path: path.to_path_buf(),
+        })
+    }
+
+    /// Ключи запусков вместе с позицией записи файла, в которой лежит строка
+    fn read_keys(&self) -> Result<Vec<(u64, JobKey)>, String> {
+        let records = match Records::open(&self.path) {
+            Ok(records) => records,
+            Err(_) => return Ok(vec![]),
+        };
+
+        let mut keys: Vec<(u64, JobKey)> = vec![];
+        for (start, record) in records {
+            for line in record.split(|byte| *byte == b'\n') {
+                if line.is_empty() {
+                    continue;
+                }
+                let row: StoredRow = serde_json::from_slice(line)
+                    .map_err(|e| format!("{:?}:{}: {}", self.path, keys.len() + 1, e))?;
+                keys.push((
+                    start,
+                    JobKey {
+                        instance_id: row.instance_id,
+                        config_hash: row.config_hash,
+                        repetition: row.repetition,
+                    },
+                ));
+            }
+        }
+
+        Ok(keys)
+    }
+}
+
+impl ResultSink for JsonlSink {
+    fn stored(&self) -> Result<Option<Vec<JobKey>>, String> {
+        Ok(Some(
+            self.read_keys()?.into_iter().map(|(_, key)| key).collect(),
+        ))
+    }
+
+    fn rollback(&mut self, done: &HashSet<JobKey>) -> Result<usize, String> {
+        let keys = self.read_keys()?;
+        let first_unfinished = match keys.iter().position(|(_, key)| !done.contains(key)) {
+            Some(idx) => idx,
+            None => return Ok(0),
+        };
+
+        let len = keys[first_unfinished].0;
+        AppendFile::truncate(&self.path, len)?;
+        Ok(keys.iter().filter(|(start, _)| *start >= len).count())
+    }
+
+    fn write_result(&mut self, row: &RunAlgoResult) -> Result<(), String> {
+        if row.is_empty() {
+            return Ok(());
+        }
+
+        let value = json!({
+            "instance_id": row.instance_id,
+            "config_hash": row.config_hash,
+            "repetition": row.repetition,
+            "seed": row.seed,
+            "matrices_hash": row.matrices_hash,
+            "metadata": row.metadata,
+            "tasks": row.tasks,
+            "criteria": row.criteria,
+            "preprocessing": row.preprocessing,
+            "algo": row.algo,
+            "iterations": row.iterations,
+            "status": row.status,
+            "error": row.error,
+            "stop_reason": row.stop_reason,
+            "calculation_time": row.calculation_time,
+        });
+        let mut line = serde_json::to_vec(&value).map_err(|e| e.to_string())?;
+        line.push(b'\n');
+
+        self.file.append(&line)
+    }
+}
@@ -0,0 +1,227 @@
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::path::Path;
+
+use crate::append_file::{scan_csv, AppendFile};
+use crate::classes::run_algo::RunAlgoResult;
+use crate::file_opener::FileManager;
+use crate::instances::Instance;
+use crate::journal::{JobKey, Journal};
+
+mod csv_dataset;
+mod iterations;
+mod jsonl;
+mod parquet_dataset;
+mod sqlite;
+mod stdout;
+
+pub use sqlite::{SqliteStore, DEFAULT_QUERY};
+
+/// Приёмник результатов расчёта
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum SinkKind {
+    /// dataset.csv, вложенные поля записаны как JSON
+    Csv,
+    /// dataset.jsonl, строка-объект на каждый запуск
+    Jsonl,
+    /// Каталог dataset/ с part-файлами Parquet и типизированными столбцами
+    Parquet,
+    /// results.sqlite с таблицами instances, configs, runs и iterations
+    Sqlite,
+    /// iterations.csv: строка на каждую итерацию запуска
+    Iterations,
+    /// Краткая строка о каждом запуске в консоль
+    Stdout,
+}
+
+pub fn default_sinks() -> Vec<SinkKind> {
+    vec![SinkKind::Csv]
+}
+
+pub struct SinkOptions<'a> {
+    /// Сколько строк копить перед записью part-файла Parquet
+    pub rows_per_part: usize,
+    /// Добавлять маршрут в iterations.csv
+    pub iterations_path: bool,
+    pub instances: &'a [Instance],
+}
+
+/// Приёмник результатов. Запуск попадает в журнал, когда все приёмники
+/// сохранили его на диск, то есть ни у одного нет несохранённых строк.
+pub trait ResultSink: Send {
+    /// Запуски, результаты которых уже сохранены; `None` - приёмник ничего
+    /// не хранит и не участвует в восстановлении журнала
+    fn stored(&self) -> Result<Option<Vec<JobKey>>, String>;
+
+    /// Удаляет результаты запусков, которых нет в журнале (они будут посчитаны
+    /// заново). Возвращает число удалённых записей.
+    fn rollback(&mut self, _done: &HashSet<JobKey>) -> Result<usize, String> {
+        Ok(0)
+    }
+
+    fn write_result(&mut self, row: &RunAlgoResult) -> Result<(), String>;
+
+    /// Файл матрицы обработан всеми алгоритмами
+    fn write_progress(&mut self, _file_name: &str) -> Result<(), String> {
+        Ok(())
+    }
+
+    /// Количество принятых, но ещё не сохранённых на диск результатов
+    fn pending(&self) -> usize {
+        0
+    }
+
+    fn finish(&mut self) -> Result<(), String> {
+        Ok(())
+    }
+}
+
+pub fn open(
+    kind: SinkKind,
+    file_manager: &FileManager,
+    options: &SinkOptions,
+) -> Result<Box<dyn ResultSink>, String> {
+    Ok(match kind {
+        SinkKind::Csv => Box::new(csv_dataset::CsvSink::open(&file_manager.dataset_path)?),
+        SinkKind::Jsonl => Box::new(jsonl::JsonlSink::open(&file_manager.jsonl_path)?),
+        SinkKind::Parquet => Box::new(parquet_dataset::ParquetSink::open(
+            &file_manager.parquet_dir,
+            options.rows_per_part,
+        )?),
+        SinkKind::Sqlite => {
+            let mut store = SqliteStore::open(&file_manager.sqlite_path)?;
+            store.add_instances(options.instances)?;
+            Box::new(store)
+        }
+        SinkKind::Iterations => Box::new(iterations::IterationsTable::open(
+            &file_manager.iterations_path,
+            options.instances,
+            options.iterations_path,
+        )?),
+        SinkKind::Stdout => Box::new(stdout::StdoutSink),
+    })
+}
+
+/// Приводит журнал и приёмники к согласованному состоянию после аварийного
+/// завершения: запуски, сохранённые всеми хранящими приёмниками, заносятся в
+/// журнал, а частично сохранённые удаляются оттуда, где это возможно.
+/// Возвращает количество восстановленных записей журнала и удалённых записей.
+pub fn recover(
+    journal: &mut Journal,
+    sinks: &mut [Box<dyn ResultSink>],
+) -> Result<(usize, usize), String> {
+    let mut stored: Option<HashSet<JobKey>> = None;
+    for sink in sinks.iter() {
+        if let Some(keys) = sink.stored()? {
+            let keys: HashSet<JobKey> = keys.into_iter().collect();
+            stored = Some(match stored {
+                Some(previous) => previous.intersection(&keys).cloned().collect(),
+                None => keys,
+            });
+        }
+    }
+    let recovered = journal.recover(stored.unwrap_or_default().into_iter().collect())?;
+
+    let mut dropped = 0;
+    for sink in sinks.iter_mut() {
+        dropped += sink.rollback(journal.done())?;
+    }
+
+    Ok((recovered, dropped))
+}
+
+/// Запуски в CSV-файле, первые три столбца которого - `instance_id`,
+/// `config_hash` и `repetition`
+fn csv_keys(path: &Path) -> Result<Vec<JobKey>, String> {
+    Ok(csv_rows(path)?.into_iter().map(|(_, key)| key).collect())
+}
+
+/// Строки CSV-файла без заголовка вместе с позицией записи файла, в которой
+/// лежит строка. Сжатый файл можно обрезать только по границе записи (фрейма).
+fn csv_rows(path: &Path) -> Result<Vec<(u64, JobKey)>, String> {
+    if !path.exists() {
+        return Ok(vec![]);
+    }
+
+    let mut rows: Vec<(u64, JobKey)> = vec![];
+    let mut header = true;
+    scan_csv(path, |start, row| {
+        if header {
+            header = false;
+            return Ok(());
+        }
+        let field = |idx: usize, name: &str| {
+            row.get(idx)
+                .ok_or_else(|| format!("{:?}: в строке нет столбца {}", path, name))
+        };
+        let repetition = field(2, "repetition")?;
+        rows.push((
+            start,
+            JobKey {
+                instance_id: field(0, "instance_id")?.to_string(),
+                config_hash: field(1, "config_hash")?.to_string(),
+                repetition: repetition
+                    .parse()
+                    .map_err(|e| format!("{:?}: repetition {:?}: {}", path, repetition, e))?,
+            },
+        ));
+        Ok(())
+    })?;
+
+    Ok(rows)
+}
+
+/// Строки запусков пишутся раньше, чем запуск попадает в журнал, поэтому
+/// строки незавершённых запусков находятся в конце файла и отрезаются
+fn truncate_csv_tail(path: &Path, done: &HashSet<JobKey>) -> Result<usize, String> {
+    let rows = csv_rows(path)?;
+    let len = match rows.iter().find(|(_, key)| !done.contains(key)) {
+        Some((start, _)) => *start,
+        None => return Ok(0),
+    };
+
+    AppendFile::truncate(path, len)?;
+    Ok(rows.iter().filter(|(start, _)| *start >= len).count())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::fs;
+    use std::process;
+
+    #[test]
+    fn truncate_csv_tail_keeps_quoted_newlines() {
+        let dir = env::temp_dir().join(format!("sinks-{}-truncate", process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("dataset.csv");
+
+        let mut file = AppendFile::open(&path).unwrap();
+        let mut writer = csv::Writer::from_writer(vec![]);
+        writer
+            .write_record(["instance_id", "config_hash", "repetition", "error"])
+            .unwrap();
+        writer.write_record(["a", "h", "0", "line\nbreak"]).unwrap();
+        file.append(&writer.into_inner().unwrap()).unwrap();
+        let len = fs::metadata(&path).unwrap().len();
+        let mut writer = csv::Writer::from_writer(vec![]);
+        writer.write_record(["b", "h", "1", "line\nbreak"]).unwrap();
+        file.append(&writer.into_inner().unwrap()).unwrap();
+
+        let key = |instance_id: &str, repetition| JobKey {
+            instance_id: instance_id.to_string(),
+            config_hash: "h".to_string(),
+            repetition,
+        };
+        assert_eq!(csv_keys(&path).unwrap(), [key("a", 0), key("b", 1)]);
+
+        let done = HashSet::from([key("a", 0)]);
+        assert_eq!(truncate_csv_tail(&path, &done).unwrap(), 1);
+        assert_eq!(fs::metadata(&path).unwrap().len(), len);
+        assert_eq!(csv_keys(&path).unwrap(), [key("a", 0)]);
+    }
+}
@@ -0,0 +1,566 @@
+use arrow::array::{
+    ArrayRef, AsArray, BooleanArray, Float32Array, Float64Array, Int64Array, ListArray,
+    StringArray, StructArray, UInt64Array,
+};
+use arrow::buffer::OffsetBuffer;
+use arrow::compute::filter_record_batch;
+use arrow::datatypes::{DataType, Field, FieldRef, Fields, Schema, SchemaRef, UInt64Type};
+use arrow::error::ArrowError;
+use arrow::record_batch::RecordBatch;
+use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+use parquet::arrow::{ArrowWriter, ProjectionMask};
+use parquet::basic::Compression;
+use parquet::file::properties::WriterProperties;
+use std::collections::HashSet;
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use super::ResultSink;
+use crate::classes::algorithm_params::AlgorithmParams;
+use crate::classes::run_algo::RunAlgoResult;
+use crate::journal::JobKey;
+
+const PART_PREFIX: &str = "part-";
+const PART_EXTENSION: &str = "parquet";
+const TMP_EXTENSION: &str = "parquet.tmp";
+
+/// Датасет в виде набора part-файлов Parquet. Строки копятся в памяти и
+/// сбрасываются отдельным файлом, который появляется под своим именем только
+/// целиком записанным; пока строки не записаны, их запуски не попадают в журнал.
+pub struct ParquetSink {
+    dir: PathBuf,
+    rows: Vec<RunAlgoResult>,
+    rows_per_part: usize,
+    next_part: usize,
+}
+
+impl ParquetSink {
+    pub fn open(dir: &Path, rows_per_part: usize) -> Result<Self, String> {
+        fs::create_dir_all(dir).map_err(|e| format!("Не удалось создать {:?}: {}", dir, e))?;
+
+        let mut next_part = 0;
+        for path in list_files(dir)? {
+            let name = path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .unwrap_or("");
+            if name.ends_with(TMP_EXTENSION) {
+                // Файл, запись которого прервалась: его строки не попали в журнал
+                fs::remove_file(&path).map_err(|e| format!("{:?}: {}", path, e))?;
+            } else if let Some(idx) = part_index(name) {
+                next_part = next_part.max(idx + 1);
+            }
+        }
+
+        Ok(Self {
+            dir: dir.to_path_buf(),
+            rows: vec![],
+            rows_per_part: rows_per_part.max(1),
+            next_part,
+        })
+    }
+
+    fn flush(&mut self) -> Result<(), String> {
+        if self.rows.is_empty() {
+            return Ok(());
+        }
+
+        let path = self.dir.join(format!(
+            "{}{:05}.{}",
+            PART_PREFIX, self.next_part, PART_EXTENSION
+        ));
+        let batch = record_batch(&self.rows)
+            .map_err(|e| format!("Не удалось записать {:?}: {}", path, e))?;
+        write_part(&path, batch.schema(), &[batch])?;
+
+        self.next_part += 1;
+        self.rows.clear();
+        Ok(())
+    }
+}
+
+impl ResultSink for ParquetSink {
+    fn stored(&self) -> Result<Option<Vec<JobKey>>, String> {
+        keys_in(&self.dir).map(Some)
+    }
+
+    /// Part-файлы со строками незавершённых запусков переписываются без этих
+    /// строк (так же атомарно, как при записи), а опустевшие удаляются
+    fn rollback(&mut self, done: &HashSet<JobKey>) -> Result<usize, String> {
+        let mut dropped = 0;
+        for path in part_files(&self.dir)? {
+            let map_err = |e: &dyn std::fmt::Display| format!("{:?}: {}", path, e);
+
+            let file = File::open(&path).map_err(|e| map_err(&e))?;
+            let builder =
+                ParquetRecordBatchReaderBuilder::try_new(file).map_err(|e| map_err(&e))?;
+            let schema = builder.schema().clone();
+            let reader = builder.build().map_err(|e| map_err(&e))?;
+
+            let mut batches: Vec<RecordBatch> = vec![];
+            let mut removed = 0;
+            for batch in reader {
+                let batch = batch.map_err(|e| map_err(&e))?;
+                let keep: BooleanArray = row_keys(&batch)
+                    .map_err(|e| map_err(&e))?
+                    .iter()
+                    .map(|key| Some(key.as_ref().is_some_and(|key| done.contains(key))))
+                    .collect();
+                removed += keep.false_count();
+                batches.push(filter_record_batch(&batch, &keep).map_err(|e| map_err(&e))?);
+            }
+            if removed == 0 {
+                continue;
+            }
+
+            if batches.iter().all(|batch| batch.num_rows() == 0) {
+                fs::remove_file(&path).map_err(|e| map_err(&e))?;
+            } else {
+                write_part(&path, schema, &batches)?;
+            }
+            dropped += removed;
+        }
+
+        Ok(dropped)
+    }
+
+    fn write_result(&mut self, row: &RunAlgoResult) -> Result<(), String> {
+        if row.is_empty() {
+            return Ok(());
+        }
+        self.rows.push(row.clone());
+        if self.rows.len() >= self.rows_per_part {
+            self.flush()?;
+        }
+        Ok(())
+    }
+
+    fn pending(&self) -> usize {
+        self.rows.len()
+    }
+
+    fn finish(&mut self) -> Result<(), String> {
+        self.flush()
+    }
+}
+
+/// Запуски, уже сохранённые в part-файлах каталога
+fn keys_in(dir: &Path) -> Result<Vec<JobKey>, String> {
+    let mut keys: Vec<JobKey> = vec![];
+    if !dir.is_dir() {
+        return Ok(keys);
+    }
+
+    for path in part_files(dir)? {
+        let map_err = |e: &dyn std::fmt::Display| format!("{:?}: {}", path, e);
+
+        let file = File::open(&path).map_err(|e| map_err(&e))?;
+        let builder = ParquetRecordBatchReaderBuilder::try_new(file).map_err(|e| map_err(&e))?;
+        let mask = ProjectionMask::roots(builder.parquet_schema(), [0, 1, 2]);
+        let reader = builder
+            .with_projection(mask)
+            .build()
+            .map_err(|e| map_err(&e))?;
+
+        for batch in reader {
+            let batch = batch.map_err(|e| map_err(&e))?;
+            keys.extend(
+                row_keys(&batch)
+                    .map_err(|e| map_err(&e))?
+                    .into_iter()
+                    .flatten(),
+            );
+        }
+    }
+
+    Ok(keys)
+}
+
+/// Ключ запуска каждой строки; первые три столбца - `instance_id`,
+/// `config_hash` и `repetition`
+fn row_keys(batch: &RecordBatch) -> Result<Vec<Option<JobKey>>, String> {
+    let instance_ids = batch.column(0).as_string::<i32>();
+    let config_hashes = batch.column(1).as_string::<i32>();
+    let repetitions = batch
+        .column(2)
+        .as_primitive_opt::<UInt64Type>()
+        .ok_or_else(|| "нет столбца repetition".to_string())?;
+
+    Ok(instance_ids
+        .iter()
+        .zip(config_hashes.iter())
+        .zip(repetitions.iter())
+        .map(|((instance_id, config_hash), repetition)| {
+            Some(JobKey {
+                instance_id: instance_id?.to_string(),
+                config_hash: config_hash?.to_string(),
+                repetition: repetition? as usize,
+            })
+        })
+        .collect())
+}
+
+/// Записывает part-файл: он появляется под своим именем только целиком
+/// записанным, прежнее содержимое заменяется атомарно
+fn write_part(path: &Path, schema: SchemaRef, batches: &[RecordBatch]) -> Result<(), String> {
+    let tmp_path = path.with_extension(TMP_EXTENSION);
+    let map_err = |e: &dyn std::fmt::Display| format!("Не удалось записать {:?}: {}", path, e);
+
+    let file = File::create(&tmp_path).map_err(|e| map_err(&e))?;
+    let properties = WriterProperties::builder()
+        .set_compression(Compression::SNAPPY)
+        .build();
+    let mut writer =
+        ArrowWriter::try_new(file, schema, Some(properties)).map_err(|e| map_err(&e))?;
+    for batch in batches {
+        writer.write(batch).map_err(|e| map_err(&e))?;
+    }
+    writer
+        .into_inner()
+        .and_then(|file| file.sync_all().map_err(Into::into))
+        .map_err(|e| map_err(&e))?;
+    fs::rename(&tmp_path, path).map_err(|e| map_err(&e))
+}
+
+fn part_files(dir: &Path) -> Result<Vec<PathBuf>, String> {
+    Ok(list_files(dir)?
+        .into_iter()
+        .filter(|path| {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .and_then(part_index)
+                .is_some()
+        })
+        .collect())
+}
+
+fn list_files(dir: &Path) -> Result<Vec<PathBuf>, String> {
+    let mut files: Vec<PathBuf> = fs::read_dir(dir)
+        .map_err(|e| format!("Не удалось открыть {:?}: {}", dir, e))?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .collect();
+    files.sort();
+    Ok(files)
+}
+
+fn part_index(file_name: &str) -> Option<usize> {
+    file_name
+        .strip_prefix(PART_PREFIX)?
+        .strip_suffix(PART_EXTENSION)?
+        .strip_suffix('.')?
+        .parse()
+        .ok()
+}
+
+fn item_field(data_type: DataType) -> FieldRef {
+    Arc::new(Field::new("item", data_type, false))
+}
+
+fn list_of(data_type: DataType) -> DataType {
+    DataType::List(item_field(data_type))
+}
+
+fn result_fields() -> Fields {
+    Fields::from(vec![
+        Field::new("criterion", DataType::Utf8, false),
+        Field::new("value", DataType::Float64, false),
+    ])
+}
+
+fn iteration_fields() -> Fields {
+    Fields::from(vec![
+        Field::new("iter_num", DataType::UInt64, false),
+        Field::new("calc_time", DataType::UInt64, false),
+        Field::new("evaluations", DataType::UInt64, false),
+        Field::new("weight", DataType::Float32, false),
+        Field::new("results", list_of(DataType::Struct(result_fields())), false),
+        Field::new("path", list_of(DataType::UInt64), false),
+    ])
+}
+
+/// Параметры алгоритмов раскладываются по типизированным столбцам с префиксом
+/// семейства; у строк другого семейства такие столбцы пустые
+fn schema() -> SchemaRef {
+    let column = |name: &str, data_type: DataType| Field::new(name, data_type, true);
+
+    Arc::new(Schema::new(vec![
+        Field::new("instance_id", DataType::Utf8, false),
+        Field::new("config_hash", DataType::Utf8, false),
+        Field::new("repetition", DataType::UInt64, false),
+        Field::new("seed", DataType::UInt64, false),
+        Field::new("matrices_hash", DataType::Utf8, false),
+        Field::new("metadata", DataType::Utf8, false),
+        Field::new("criteria", list_of(DataType::Utf8), false),
+        Field::new("preprocessing", DataType::Utf8, false),
+        Field::new("algo", DataType::Utf8, false),
+        Field::new("algo_type", DataType::Utf8, false),
+        column("actors_count", DataType::UInt64),
+        column("ac_alpha", DataType::Float64),
+        column("ac_beta", DataType::Float64),
+        column("ac_q", DataType::Float64),
+        column("ac_p", DataType::Float64),
+        column("bc_workers_part", DataType::Float32),
+        column("bc_regenerate_func", DataType::Utf8),
+        column("ga_p_mutation", DataType::Float32),
+        column("ga_select_func", DataType::Utf8),
+        column("ga_mutate_func", DataType::Utf8),
+        column("sa_initial_temperature", DataType::Float64),
+        column("sa_final_temperature", DataType::Float64),
+        column("sa_cooling_rate", DataType::Float64),
+        column("sa_mutate_func", DataType::Utf8),
+        column("tasks", DataType::Utf8),
+        Field::new(
+            "iterations",
+            list_of(DataType::Struct(iteration_fields())),
+            false,
+        ),
+        Field::new("status", DataType::Utf8, false),
+        Field::new("error", DataType::Utf8, true),
+        Field::new("stop_reason", DataType::Utf8, false),
+        Field::new("calculation_time", DataType::Int64, false),
+    ]))
+}
+
+#[derive(Default)]
+struct ParamColumns {
+    actors_count: Vec<Option<u64>>,
+    ac_alpha: Vec<Option<f64>>,
+    ac_beta: Vec<Option<f64>>,
+    ac_q: Vec<Option<f64>>,
+    ac_p: Vec<Option<f64>>,
+    bc_workers_part: Vec<Option<f32>>,
+    bc_regenerate_func: Vec<Option<&'static str>>,
+    ga_p_mutation: Vec<Option<f32>>,
+    ga_select_func: Vec<Option<&'static str>>,
+    ga_mutate_func: Vec<Option<&'static str>>,
+    sa_initial_temperature: Vec<Option<f64>>,
+    sa_final_temperature: Vec<Option<f64>>,
+    sa_cooling_rate: Vec<Option<f64>>,
+    sa_mutate_func: Vec<Option<&'static str>>,
+}
+
+impl ParamColumns {
+    fn push(&mut self, params: &AlgorithmParams) {
+        let (mut actors_count, mut ac, mut bc, mut ga, mut sa) = (None, None, None, None, None);
+        match params {
+            AlgorithmParams::AC {
+                alpha,
+                beta,
+                q,
+                p,
+                actors_count: count,
+            } => {
+                actors_count = Some(*count as u64);
+                ac = Some((*alpha, *beta, *q, *p));
+            }
+            AlgorithmParams::BC {
+                workers_part,
+                research_func,
+                actors_count: count,
+            } => {
+                actors_count = Some(*count as u64);
+                bc = Some((*workers_part, *research_func));
+            }
+            AlgorithmParams::GA {
+                p_mutation,
+                select_func,
+                mutate_func,
+                actors_count: count,
+            } => {
+                actors_count = Some(*count as u64);
+                ga = Some((*p_mutation, *select_func, *mutate_func));
+            }
+            AlgorithmParams::SA {
+                initial_temperature,
+                final_temperature,
+                cooling_rate,
+                mutate_func,
+            } => {
+                sa = Some((
+                    *initial_temperature,
+                    *final_temperature,
+                    *cooling_rate,
+                    *mutate_func,
+                ));
+            }
+        }
+
+        self.actors_count.push(actors_count);
+        self.ac_alpha.push(ac.map(|v| v.0));
+        self.ac_beta.push(ac.map(|v| v.1));
+        self.ac_q.push(ac.map(|v| v.2));
+        self.ac_p.push(ac.map(|v| v.3));
+        self.bc_workers_part.push(bc.map(|v| v.0));
+        self.bc_regenerate_func.push(bc.map(|v| v.1));
+        self.ga_p_mutation.push(ga.map(|v| v.0));
+        self.ga_select_func.push(ga.map(|v| v.1));
+        self.ga_mutate_func.push(ga.map(|v| v.2));
+        self.sa_initial_temperature.push(sa.map(|v| v.0));
+        self.sa_final_temperature.push(sa.map(|v| v.1));
+        self.sa_cooling_rate.push(sa.map(|v| v.2));
+        self.sa_mutate_func.push(sa.map(|v| v.3));
+    }
+
+    fn into_arrays(self) -> Vec<ArrayRef> {
+        vec![
+            Arc::new(UInt64Array::from(self.actors_count)),
+            Arc::new(Float64Array::from(self.ac_alpha)),
+            Arc::new(Float64Array::from(self.ac_beta)),
+            Arc::new(Float64Array::from(self.ac_q)),
+            Arc::new(Float64Array::from(self.ac_p)),
+            Arc::new(Float32Array::from(self.bc_workers_part)),
+            Arc::new(StringArray::from(self.bc_regenerate_func)),
+            Arc::new(Float32Array::from(self.ga_p_mutation)),
+            Arc::new(StringArray::from(self.ga_select_func)),
+            Arc::new(StringArray::from(self.ga_mutate_func)),
+            Arc::new(Float64Array::from(self.sa_initial_temperature)),
+            Arc::new(Float64Array::from(self.sa_final_temperature)),
+            Arc::new(Float64Array::from(self.sa_cooling_rate)),
+            Arc::new(StringArray::from(self.sa_mutate_func)),
+        ]
+    }
+}
+
+fn to_json<T: serde::Serialize>(value: &T) -> Result<String, ArrowError> {
+    serde_json::to_string(value).map_err(|e| ArrowError::ExternalError(Box::new(e)))
+}
+
+fn iterations_array(rows: &[RunAlgoResult]) -> Result<ArrayRef, ArrowError> {
+    let iterations: Vec<_> = rows.iter().flat_map(|row| &row.iterations).collect();
+
+    // Порядок критериев в FxHashMap не определён, в файле они отсортированы по имени
+    let results: Vec<Vec<(&String, &f64)>> = iterations
+        .iter()
+        .map(|iteration| {
+            let mut results: Vec<_> = iteration.results.iter().collect();
+            results.sort_by(|a, b| a.0.cmp(b.0));
+            results
+        })
+        .collect();
+    let results_struct = StructArray::try_new(
+        result_fields(),
+        vec![
+            // У сцепленного итератора нет верхней границы размера, которой
+            // требует StringArray::from_iter_values
+            Arc::new(StringArray::from_iter_values(
+                results
+                    .iter()
+                    .flatten()
+                    .map(|(name, _)| name.as_str())
+                    .collect::<Vec<&str>>(),
+            )),
+            Arc::new(Float64Array::from_iter_values(
+                results.iter().flatten().map(|(_, value)| **value),
+            )),
+        ],
+        None,
+    )?;
+    let results_list = ListArray::try_new(
+        item_field(DataType::Struct(result_fields())),
+        OffsetBuffer::from_lengths(results.iter().map(Vec::len)),
+        Arc::new(results_struct),
+        None,
+    )?;
+
+    let path = ListArray::try_new(
+        item_field(DataType::UInt64),
+        OffsetBuffer::from_lengths(iterations.iter().map(|iteration| iteration.path.len())),
+        Arc::new(UInt64Array::from_iter_values(iterations.iter().flat_map(
+            |iteration| iteration.path.iter().map(|node| *node as u64),
+        ))),
+        None,
+    )?;
+
+    let iterations_struct = StructArray::try_new(
+        iteration_fields(),
+        vec![
+            Arc::new(UInt64Array::from_iter_values(
+                iterations.iter().map(|iteration| iteration.iter_num as u64),
+            )),
+            Arc::new(UInt64Array::from_iter_values(
+                iterations
+                    .iter()
+                    .map(|iteration| iteration.calc_time as u64),
+            )),
+            Arc::new(UInt64Array::from_iter_values(
+                iterations
+                    .iter()
+                    .map(|iteration| iteration.evaluations as u64),
+            )),
+            Arc::new(Float32Array::from_iter_values(
+                iterations.iter().map(|iteration| iteration.weight),
+            )),
+            Arc::new(results_list),
+            Arc::new(path),
+        ],
+        None,
+    )?;
+
+    Ok(Arc::new(ListArray::try_new(
+        item_field(DataType::Struct(iteration_fields())),
+        OffsetBuffer::from_lengths(rows.iter().map(|row| row.iterations.len())),
+        Arc::new(iterations_struct),
+        None,
+    )?))
+}
+
+fn record_batch(rows: &[RunAlgoResult]) -> Result<RecordBatch, ArrowError> {
+    let strings = |f: fn(&RunAlgoResult) -> &str| -> ArrayRef {
+        Arc::new(StringArray::from_iter_values(rows.iter().map(f)))
+    };
+
+    let mut metadata: Vec<String> = vec![];
+    let mut preprocessing: Vec<String> = vec![];
+    let mut algo: Vec<String> = vec![];
+    let mut tasks: Vec<Option<String>> = vec![];
+    let mut params = ParamColumns::default();
+    for row in rows {
+        metadata.push(to_json(&row.metadata)?);
+        preprocessing.push(to_json(&row.preprocessing)?);
+        algo.push(to_json(&row.algo)?);
+        tasks.push(row.tasks.as_ref().map(to_json).transpose()?);
+        params.push(&row.algo);
+    }
+
+    let criteria = ListArray::try_new(
+        item_field(DataType::Utf8),
+        OffsetBuffer::from_lengths(rows.iter().map(|row| row.criteria.len())),
+        Arc::new(StringArray::from_iter_values(
+            rows.iter()
+                .flat_map(|row| row.criteria.iter())
+                .collect::<Vec<&String>>(),
+        )),
+        None,
+    )?;
+
+    let mut columns: Vec<ArrayRef> = vec![
+        strings(|row| row.instance_id.as_str()),
+        strings(|row| row.config_hash.as_str()),
+        Arc::new(UInt64Array::from_iter_values(
+            rows.iter().map(|row| row.repetition as u64),
+        )),
+        Arc::new(UInt64Array::from_iter_values(
+            rows.iter().map(|row| row.seed),
+        )),
+        strings(|row| row.matrices_hash.as_str()),
+        Arc::new(StringArray::from_iter_values(metadata)),
+        Arc::new(criteria),
+        Arc::new(StringArray::from_iter_values(preprocessing)),
+        Arc::new(StringArray::from_iter_values(algo)),
+        strings(|row| row.algo.type_name()),
+    ];
+    columns.extend(params.into_arrays());
+    columns.push(Arc::new(StringArray::from(tasks)));
+    columns.push(iterations_array(rows)?);
+    columns.push(strings(|row| row.status.as_str()));
+    columns.push(Arc::new(StringArray::from_iter(
+        rows.iter().map(|row| row.error.as_deref()),
+    )));
+    columns.push(strings(|row| row.stop_reason.as_str()));
+    columns.push(Arc::new(Int64Array::from_iter_values(
+        rows.iter().map(|row| row.calculation_time),
+    )));
+
+    RecordBatch::try_new(schema(), columns)
+}
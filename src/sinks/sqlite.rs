@@ -0,0 +1,377 @@
+use rusqlite::types::ValueRef;
+use rusqlite::{params, Connection, OpenFlags};
+use std::collections::HashSet;
+use std::path::Path;
+
+use super::ResultSink;
+use crate::classes::run_algo::RunAlgoResult;
+use crate::classes::stopping;
+use crate::instances::Instance;
+use crate::journal::JobKey;
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS instances (
+    id TEXT PRIMARY KEY,
+    size INTEGER,
+    criteria TEXT NOT NULL,
+    metadata TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS configs (
+    hash TEXT PRIMARY KEY,
+    algo_type TEXT NOT NULL,
+    params TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS runs (
+    id INTEGER PRIMARY KEY,
+    instance_id TEXT NOT NULL REFERENCES instances(id),
+    config_hash TEXT NOT NULL REFERENCES configs(hash),
+    repetition INTEGER NOT NULL,
+    seed INTEGER NOT NULL,
+    matrices_hash TEXT NOT NULL,
+    preprocessing TEXT NOT NULL,
+    tasks TEXT,
+    best_weight REAL,
+    iterations_count INTEGER NOT NULL,
+    evaluations INTEGER NOT NULL,
+    status TEXT NOT NULL,
+    error TEXT,
+    stop_reason TEXT NOT NULL,
+    calculation_time INTEGER NOT NULL,
+    UNIQUE (instance_id, config_hash, repetition)
+);
+CREATE TABLE IF NOT EXISTS iterations (
+    run_id INTEGER NOT NULL REFERENCES runs(id),
+    iter_num INTEGER NOT NULL,
+    calc_time INTEGER NOT NULL,
+    evaluations INTEGER NOT NULL,
+    weight REAL NOT NULL,
+    results TEXT NOT NULL,
+    path TEXT NOT NULL,
+    PRIMARY KEY (run_id, iter_num)
+);
+";
+
+/// Средний лучший результат по семействам алгоритмов и размерам экземпляров
+pub const DEFAULT_QUERY: &str = "
+SELECT c.algo_type, i.size, COUNT(*) AS runs,
+       AVG(r.best_weight) AS mean_best_weight,
+       MAX(r.best_weight) AS max_best_weight,
+       AVG(r.iterations_count) AS mean_iterations,
+       AVG(r.calculation_time) / 1000.0 AS mean_time_s
+FROM runs r
+JOIN configs c ON c.hash = r.config_hash
+JOIN instances i ON i.id = r.instance_id
+WHERE r.best_weight IS NOT NULL
+GROUP BY c.algo_type, i.size
+ORDER BY c.algo_type, i.size
+";
+
+/// Результаты расчёта в SQLite. Запуск со всеми итерациями записывается
+/// одной транзакцией, поэтому таблица `runs` одновременно служит журналом
+/// завершённых запусков.
+pub struct SqliteStore {
+    connection: Connection,
+}
+
+impl SqliteStore {
+    pub fn open(path: &Path) -> Result<Self, String> {
+        let map_err = |e: rusqlite::Error| format!("{:?}: {}", path, e);
+
+        let connection = Connection::open(path).map_err(map_err)?;
+        connection
+            .pragma_update(None, "journal_mode", "WAL")
+            .and_then(|_| connection.pragma_update(None, "synchronous", "FULL"))
+            .and_then(|_| connection.execute_batch(SCHEMA))
+            .map_err(map_err)?;
+
+        Ok(Self { connection })
+    }
+
+    /// Базу только читают: схема и режим журнала не меняются, а запись
+    /// запрещена, поэтому запросы можно выполнять во время расчёта
+    pub fn open_read_only(path: &Path) -> Result<Self, String> {
+        let connection = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY)
+            .map_err(|e| format!("{:?}: {}", path, e))?;
+
+        Ok(Self { connection })
+    }
+
+    pub fn add_instances(&mut self, instances: &[Instance]) -> Result<(), String> {
+        let transaction = self.connection.transaction().map_err(|e| e.to_string())?;
+        {
+            let mut statement = transaction
+                .prepare(
+                    "INSERT OR IGNORE INTO instances (id, size, criteria, metadata)
+                     VALUES (?1, ?2, ?3, ?4)",
+                )
+                .map_err(|e| e.to_string())?;
+            for instance in instances {
+                statement
+                    .execute(params![
+                        instance.id,
+                        instance.size.map(|size| size as i64),
+                        serde_json::to_string(&instance.criteria()).unwrap(),
+                        serde_json::to_string(&instance.metadata).unwrap(),
+                    ])
+                    .map_err(|e| e.to_string())?;
+            }
+        }
+        transaction.commit().map_err(|e| e.to_string())
+    }
+
+    fn insert(&mut self, row: &RunAlgoResult) -> Result<(), String> {
+        let best_weight = row
+            .iterations
+            .iter()
+            .map(|iteration| iteration.weight)
+            .reduce(f32::max);
+
+        let transaction = self.connection.transaction().map_err(|e| e.to_string())?;
+        let inserted = transaction
+            .execute(
+                "INSERT OR IGNORE INTO configs (hash, algo_type, params) VALUES (?1, ?2, ?3)",
+                params![
+                    row.config_hash,
+                    row.algo.type_name(),
+                    serde_json::to_string(&row.algo).unwrap(),
+                ],
+            )
+            .and_then(|_| {
+                transaction.execute(
+                    "INSERT INTO runs (instance_id, config_hash, repetition, seed, matrices_hash,
+                                       preprocessing, tasks, best_weight, iterations_count,
+                                       evaluations, status, error, stop_reason,
+                                       calculation_time)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)
+                     ON CONFLICT (instance_id, config_hash, repetition) DO NOTHING",
+                    params![
+                        row.instance_id,
+                        row.config_hash,
+                        row.repetition as i64,
+                        row.seed as i64,
+                        row.matrices_hash,
+                        serde_json::to_string(&row.preprocessing).unwrap(),
+                        row.tasks
+                            .as_ref()
+                            .map(|tasks| serde_json::to_string(tasks).unwrap()),
+                        best_weight.map(f64::from),
+                        row.iterations.len() as i64,
+                        stopping::evaluations(&row.iterations) as i64,
+                        row.status.as_str(),
+                        row.error,
+                        row.stop_reason,
+                        row.calculation_time,
+                    ],
+                )
+            })
+            .map_err(|e| e.to_string())?;
+        // Запуск с таким ключом уже сохранён: повторная запись ничего не меняет
+        if inserted == 0 {
+            return transaction.commit().map_err(|e| e.to_string());
+        }
+        let run_id = transaction.last_insert_rowid();
+
+        {
+            let mut statement = transaction
+                .prepare(
+                    "INSERT INTO iterations (run_id, iter_num, calc_time, evaluations, weight,
+                                             results, path)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                )
+                .map_err(|e| e.to_string())?;
+            for iteration in &row.iterations {
+                statement
+                    .execute(params![
+                        run_id,
+                        iteration.iter_num as i64,
+                        iteration.calc_time as i64,
+                        iteration.evaluations as i64,
+                        f64::from(iteration.weight),
+                        serde_json::to_string(&iteration.results).unwrap(),
+                        serde_json::to_string(&iteration.path).unwrap(),
+                    ])
+                    .map_err(|e| e.to_string())?;
+            }
+        }
+
+        transaction.commit().map_err(|e| e.to_string())
+    }
+
+    fn keys(&self) -> Result<Vec<JobKey>, String> {
+        let mut statement = self
+            .connection
+            .prepare("SELECT instance_id, config_hash, repetition FROM runs")
+            .map_err(|e| e.to_string())?;
+        let keys = statement
+            .query_map([], |row| {
+                Ok(JobKey {
+                    instance_id: row.get(0)?,
+                    config_hash: row.get(1)?,
+                    repetition: row.get::<_, i64>(2)? as usize,
+                })
+            })
+            .and_then(|rows| rows.collect())
+            .map_err(|e| e.to_string());
+        keys
+    }
+
+    fn delete(&mut self, keys: &[JobKey]) -> Result<(), String> {
+        let transaction = self.connection.transaction().map_err(|e| e.to_string())?;
+        for key in keys {
+            transaction
+                .execute(
+                    "DELETE FROM iterations WHERE run_id IN
+                     (SELECT id FROM runs
+                      WHERE instance_id = ?1 AND config_hash = ?2 AND repetition = ?3)",
+                    params![key.instance_id, key.config_hash, key.repetition as i64],
+                )
+                .and_then(|_| {
+                    transaction.execute(
+                        "DELETE FROM runs
+                         WHERE instance_id = ?1 AND config_hash = ?2 AND repetition = ?3",
+                        params![key.instance_id, key.config_hash, key.repetition as i64],
+                    )
+                })
+                .map_err(|e| e.to_string())?;
+        }
+        transaction.commit().map_err(|e| e.to_string())
+    }
+
+    /// Выполняет запрос и печатает результат таблицей
+    pub fn print_query(&self, sql: &str) -> Result<(), String> {
+        let mut statement = self.connection.prepare(sql).map_err(|e| e.to_string())?;
+        let columns: Vec<String> = statement
+            .column_names()
+            .iter()
+            .map(|name| name.to_string())
+            .collect();
+
+        let mut table: Vec<Vec<String>> = vec![columns.clone()];
+        let mut rows = statement.query([]).map_err(|e| e.to_string())?;
+        while let Some(row) = rows.next().map_err(|e| e.to_string())? {
+            let mut cells: Vec<String> = vec![];
+            for idx in 0..columns.len() {
+                cells.push(match row.get_ref(idx).map_err(|e| e.to_string())? {
+                    ValueRef::Null => "-".to_string(),
+                    ValueRef::Integer(value) => value.to_string(),
+                    ValueRef::Real(value) => format!("{:.3}", value),
+                    ValueRef::Text(value) => String::from_utf8_lossy(value).to_string(),
+                    ValueRef::Blob(value) => format!("<{} bytes>", value.len()),
+                });
+            }
+            table.push(cells);
+        }
+
+        let widths: Vec<usize> = (0..columns.len())
+            .map(|idx| {
+                table
+                    .iter()
+                    .map(|row| row[idx].chars().count())
+                    .max()
+                    .unwrap_or(0)
+            })
+            .collect();
+        for row in &table {
+            let line: Vec<String> = row
+                .iter()
+                .zip(&widths)
+                .map(|(cell, width)| format!("{:>width$}", cell, width = width))
+                .collect();
+            println!("{}", line.join("  "));
+        }
+
+        Ok(())
+    }
+}
+
+impl ResultSink for SqliteStore {
+    fn stored(&self) -> Result<Option<Vec<JobKey>>, String> {
+        self.keys().map(Some)
+    }
+
+    fn rollback(&mut self, done: &HashSet<JobKey>) -> Result<usize, String> {
+        let unfinished: Vec<JobKey> = self
+            .keys()?
+            .into_iter()
+            .filter(|key| !done.contains(key))
+            .collect();
+        self.delete(&unfinished)?;
+        Ok(unfinished.len())
+    }
+
+    /// Запуски без итераций тоже сохраняются, с пустым `best_weight`
+    fn write_result(&mut self, row: &RunAlgoResult) -> Result<(), String> {
+        self.insert(row)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::classes::algorithm_params::AlgorithmParams;
+    use crate::classes::run_algo::{RunAlgoResultIteration, RunStatus};
+    use std::env;
+    use std::fs;
+    use std::process;
+
+    #[test]
+    fn insert_skips_stored_key() {
+        let dir = env::temp_dir().join(format!("sqlite-{}-insert", process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let mut store = SqliteStore::open(&dir.join("results.sqlite")).unwrap();
+        let instance = Instance {
+            id: "a".to_string(),
+            size: Some(2),
+            matrices: vec![],
+            metadata: Default::default(),
+        };
+        store.add_instances(&[instance]).unwrap();
+
+        let iteration = |weight| RunAlgoResultIteration {
+            iter_num: 0,
+            calc_time: 1,
+            results: Default::default(),
+            path: vec![0, 1],
+            weight,
+            evaluations: 1,
+        };
+        let mut row = RunAlgoResult {
+            instance_id: "a".to_string(),
+            config_hash: "h".to_string(),
+            repetition: 0,
+            seed: 1,
+            metadata: Default::default(),
+            matrices_hash: "m".to_string(),
+            tasks: None,
+            criteria: vec![],
+            preprocessing: Default::default(),
+            algo: AlgorithmParams::SA {
+                initial_temperature: 10.0,
+                final_temperature: 1.0,
+                cooling_rate: 0.5,
+                mutate_func: "swap_indexes",
+            },
+            iterations: vec![iteration(1.0)],
+            status: RunStatus::Ok,
+            error: None,
+            stop_reason: "finished".to_string(),
+            calculation_time: 1,
+        };
+        store.write_result(&row).unwrap();
+        row.iterations = vec![iteration(2.0), iteration(3.0)];
+        store.write_result(&row).unwrap();
+
+        assert_eq!(store.keys().unwrap().len(), 1);
+        let (iterations, best_weight): (i64, f64) = store
+            .connection
+            .query_row(
+                "SELECT COUNT(*), MAX(r.best_weight) FROM iterations
+                 JOIN runs r ON r.id = iterations.run_id",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap();
+        assert_eq!((iterations, best_weight), (1, 1.0));
+    }
+}
@@ -0,0 +1,45 @@
+use super::ResultSink;
+use crate::classes::run_algo::RunAlgoResult;
+use crate::classes::stopping;
+use crate::journal::JobKey;
+
+/// Краткая сводка о каждом запуске в консоль; ничего не хранит
+pub struct StdoutSink;
+
+impl ResultSink for StdoutSink {
+    fn stored(&self) -> Result<Option<Vec<JobKey>>, String> {
+        Ok(None)
+    }
+
+    fn write_result(&mut self, row: &RunAlgoResult) -> Result<(), String> {
+        let best_weight = row
+            .iterations
+            .iter()
+            .map(|iteration| iteration.weight)
+            .reduce(f32::max);
+
+        println!(
+            "RESULT {} {} #{} {} best={} iterations={} evaluations={} status={} stop={} time={:.3}s{}",
+            row.instance_id,
+            row.config_hash,
+            row.repetition,
+            serde_json::to_string(&row.algo).unwrap(),
+            best_weight.map_or_else(|| "-".to_string(), |weight| weight.to_string()),
+            row.iterations.len(),
+            stopping::evaluations(&row.iterations),
+            row.status.as_str(),
+            row.stop_reason,
+            row.calculation_time as f64 / 1000.0,
+            row.error
+                .as_ref()
+                .map_or_else(String::new, |error| format!(" error={:?}", error))
+        );
+
+        Ok(())
+    }
+
+    fn write_progress(&mut self, file_name: &str) -> Result<(), String> {
+        println!("DONE {}", file_name);
+        Ok(())
+    }
+}
@@ -0,0 +1,125 @@
+use csv::Reader;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use crate::classes::run_algo::RunStatus;
+use crate::compression::Compression;
+
+#[derive(Deserialize)]
+struct DatasetRow {
+    algo: String,
+    iterations: String,
+    /// В датасетах до появления статуса все запуски успешные
+    #[serde(default)]
+    status: RunStatus,
+    calculation_time: i64,
+}
+
+#[derive(Deserialize)]
+struct IterationWeight {
+    weight: f32,
+}
+
+#[derive(Default)]
+struct AlgoSummary {
+    runs: usize,
+    best_weight_sum: f64,
+    best_weight_max: f64,
+    iterations_sum: usize,
+    calculation_time_sum: i64,
+    timeouts: usize,
+    errors: usize,
+    panics: usize,
+}
+
+pub fn summarize(dataset_path: &Path) -> Result<(), String> {
+    let mut reader = Compression::reader(dataset_path)
+        .map(Reader::from_reader)
+        .map_err(|e| format!("Не удалось открыть {:?}: {}", dataset_path, e))?;
+
+    let mut summaries: BTreeMap<String, AlgoSummary> = BTreeMap::new();
+    let mut broken_rows = 0;
+
+    for row in reader.deserialize::<DatasetRow>() {
+        let row = match row {
+            Ok(r) => r,
+            Err(_) => {
+                broken_rows += 1;
+                continue;
+            }
+        };
+
+        // Итоги по весу считаются только по успешным запускам, сбои
+        // подсчитываются отдельно
+        let summary = summaries.entry(row.algo).or_default();
+        match row.status {
+            RunStatus::Ok => {}
+            RunStatus::Timeout => {
+                summary.timeouts += 1;
+                continue;
+            }
+            RunStatus::Error => {
+                summary.errors += 1;
+                continue;
+            }
+            RunStatus::Panic => {
+                summary.panics += 1;
+                continue;
+            }
+            // Прерванные запуски в датасет не сохраняются
+            RunStatus::Cancelled => continue,
+        }
+
+        let iterations: Vec<IterationWeight> = match serde_json::from_str(&row.iterations) {
+            Ok(i) => i,
+            Err(_) => {
+                broken_rows += 1;
+                continue;
+            }
+        };
+        let best_weight = match iterations.iter().map(|i| i.weight).reduce(f32::max) {
+            Some(w) => w as f64,
+            None => continue,
+        };
+
+        if summary.runs == 0 || best_weight > summary.best_weight_max {
+            summary.best_weight_max = best_weight;
+        }
+        summary.runs += 1;
+        summary.best_weight_sum += best_weight;
+        summary.iterations_sum += iterations.len();
+        summary.calculation_time_sum += row.calculation_time;
+    }
+
+    println!(
+        "{:>6} {:>14} {:>14} {:>10} {:>12} {:>8} {:>6} {:>6}  algo",
+        "runs", "mean best", "max best", "mean iter", "mean time,s", "timeout", "error", "panic"
+    );
+    for (algo, summary) in &summaries {
+        let runs = summary.runs as f64;
+        // Алгоритм мог не завершить успешно ни одного запуска
+        let value = |value: f64, precision: usize| match summary.runs {
+            0 => "-".to_string(),
+            _ => format!("{:.*}", precision, value),
+        };
+        println!(
+            "{:>6} {:>14} {:>14} {:>10} {:>12} {:>8} {:>6} {:>6}  {}",
+            summary.runs,
+            value(summary.best_weight_sum / runs, 3),
+            value(summary.best_weight_max, 3),
+            value(summary.iterations_sum as f64 / runs, 1),
+            value(summary.calculation_time_sum as f64 / runs / 1000.0, 3),
+            summary.timeouts,
+            summary.errors,
+            summary.panics,
+            algo
+        );
+    }
+
+    if broken_rows > 0 {
+        println!("Пропущено повреждённых строк: {}", broken_rows);
+    }
+
+    Ok(())
+}
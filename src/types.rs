@@ -3,6 +3,6 @@ use super::classes::run_algo::RunAlgoResult;
 pub struct FileRow(pub String);
 
 pub enum SenderInfo {
-    DatasetRow(RunAlgoResult),
+    DatasetRow(Box<RunAlgoResult>),
     FileRow(FileRow),
 }
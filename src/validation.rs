@@ -0,0 +1,180 @@
+use clap::ValueEnum;
+use ndarray::Array2;
+use std::path::PathBuf;
+
+use crate::instances::{CriterionPurpose, Instance};
+use crate::loaders;
+
+/// Что делать с экземплярами, не прошедшими проверку
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
+pub enum ValidationPolicy {
+    /// Остановить расчёт до начала вычислений
+    Fail,
+    /// Пропустить некорректные экземпляры и посчитать остальные
+    Skip,
+}
+
+pub struct MatrixReport {
+    pub criterion: String,
+    pub purpose: CriterionPurpose,
+    pub path: PathBuf,
+    pub shape: Option<(usize, usize)>,
+    pub load_error: Option<String>,
+    pub nan_count: usize,
+    pub inf_count: usize,
+    pub negative_count: usize,
+    pub nonzero_diagonal: usize,
+    pub symmetric: bool,
+    pub size_matches: Option<bool>,
+}
+
+impl MatrixReport {
+    fn new(criterion: &str, purpose: CriterionPurpose, path: PathBuf) -> Self {
+        Self {
+            criterion: criterion.to_string(),
+            purpose,
+            path,
+            shape: None,
+            load_error: None,
+            nan_count: 0,
+            inf_count: 0,
+            negative_count: 0,
+            nonzero_diagonal: 0,
+            symmetric: false,
+            size_matches: None,
+        }
+    }
+
+    fn inspect(&mut self, matrix: &Array2<f64>, expected_size: Option<usize>) {
+        let (rows, cols) = matrix.dim();
+        self.shape = Some((rows, cols));
+        self.size_matches = expected_size.map(|size| rows == size && cols == size);
+
+        for &value in matrix.iter() {
+            if value.is_nan() {
+                self.nan_count += 1;
+            } else if value.is_infinite() {
+                self.inf_count += 1;
+            } else if value < 0.0 {
+                self.negative_count += 1;
+            }
+        }
+
+        if rows == cols {
+            self.nonzero_diagonal = matrix.diag().iter().filter(|v| **v != 0.0).count();
+            self.symmetric = *matrix == matrix.t();
+        }
+    }
+
+    /// Нулевая диагональ и симметричность только отображаются в отчёте:
+    /// асимметричные матрицы допустимы
+    pub fn errors(&self) -> Vec<String> {
+        let mut errors: Vec<String> = vec![];
+
+        if let Some(e) = &self.load_error {
+            errors.push(e.clone());
+            return errors;
+        }
+        if let Some((rows, cols)) = self.shape {
+            if rows != cols {
+                errors.push(format!("matrix is not square ({}x{})", rows, cols));
+            }
+            if rows < 2 {
+                errors.push(format!("matrix is too small ({}x{})", rows, cols));
+            }
+        }
+        if self.nan_count > 0 {
+            errors.push(format!("{} NaN values", self.nan_count));
+        }
+        if self.inf_count > 0 {
+            errors.push(format!("{} infinite values", self.inf_count));
+        }
+        if self.negative_count > 0 && self.purpose == CriterionPurpose::Min {
+            errors.push(format!("{} negative values", self.negative_count));
+        }
+        if self.size_matches == Some(false) {
+            errors.push("dimensions do not match the instance size".to_string());
+        }
+
+        errors
+    }
+}
+
+pub struct InstanceReport {
+    pub id: String,
+    pub size: Option<usize>,
+    pub matrices: Vec<MatrixReport>,
+    pub dims_agree: bool,
+}
+
+impl InstanceReport {
+    pub fn is_valid(&self) -> bool {
+        self.dims_agree && self.matrices.iter().all(|m| m.errors().is_empty())
+    }
+
+    pub fn print(&self) {
+        println!(
+            "{} (size {}): {}",
+            self.id,
+            self.size
+                .map_or_else(|| "?".to_string(), |size| size.to_string()),
+            if self.is_valid() { "OK" } else { "INVALID" }
+        );
+
+        for matrix in &self.matrices {
+            let shape = matrix
+                .shape
+                .map_or_else(|| "-".to_string(), |(r, c)| format!("{}x{}", r, c));
+            println!(
+                "  {:<12} {:>9} nan={} inf={} negative={} diagonal!=0: {} symmetric={} size={} {:?}",
+                matrix.criterion,
+                shape,
+                matrix.nan_count,
+                matrix.inf_count,
+                matrix.negative_count,
+                matrix.nonzero_diagonal,
+                if matrix.symmetric { "yes" } else { "no" },
+                match matrix.size_matches {
+                    Some(true) => "ok",
+                    Some(false) => "mismatch",
+                    None => "unknown",
+                },
+                matrix.path
+            );
+            for error in matrix.errors() {
+                println!("    error: {}", error);
+            }
+        }
+
+        if !self.dims_agree {
+            println!("    error: criteria matrices have different dimensions");
+        }
+    }
+}
+
+pub fn validate_instance(instance: &Instance) -> InstanceReport {
+    let mut matrices: Vec<MatrixReport> = vec![];
+
+    for criterion in &instance.matrices {
+        let mut report =
+            MatrixReport::new(&criterion.name, criterion.purpose, criterion.path.clone());
+        match loaders::load_matrix(&criterion.path, &criterion.name) {
+            Ok(matrix) => report.inspect(&matrix, instance.size),
+            Err(e) => report.load_error = Some(e),
+        }
+        matrices.push(report);
+    }
+
+    let mut shapes = matrices.iter().filter_map(|m| m.shape);
+    let dims_agree = match shapes.next() {
+        Some(first) => shapes.all(|shape| shape == first),
+        None => true,
+    };
+
+    InstanceReport {
+        id: instance.id.clone(),
+        size: instance.size,
+        matrices,
+        dims_agree,
+    }
+}